@@ -0,0 +1,975 @@
+use crate::two_tier::{calculate_competitive_ratio, offline_cost, KarlinInstance, NaiveInstance};
+use crate::util::{
+    generate_access_list, generate_access_list_with_rng, generate_exact_access_list,
+};
+use crate::{Algorithm, Simulator};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// The two-tier online algorithms that can be replayed over a fixed access list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgorithmKind {
+    Naive,
+    Karlin,
+}
+
+/// Costs of a single two-tier run of an online algorithm against the offline optimum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunSummary {
+    pub kind: AlgorithmKind,
+    pub keep_cost: u64,
+    pub recover_cost: u64,
+    pub online_cost: f64,
+    pub offline_cost: f64,
+    pub competitive_ratio: f64,
+    /// Discard thresholds sampled by the randomized algorithm during the run, or
+    /// empty for deterministic algorithms.
+    pub sampled_discard_thresholds: Vec<f64>,
+}
+
+/// Runs an online and an offline instance in lockstep over the same access list,
+/// recording after each tick the cumulative regret of the online instance, i.e.
+/// its accrued cost minus the offline instance's accrued cost.
+pub fn regret_trajectory<A: Algorithm, B: Algorithm>(
+    online: A,
+    offline: B,
+    access_list: Vec<u64>,
+    num_ticks: u64,
+) -> Vec<(u64, i64)> {
+    let mut online_sim = Simulator::new(access_list.clone(), online);
+    let mut offline_sim = Simulator::new(access_list, offline);
+    let mut trajectory = Vec::with_capacity(num_ticks as usize);
+    for _ in 0..num_ticks {
+        online_sim.tick();
+        offline_sim.tick();
+        let regret = crate::regret(
+            online_sim.node.total_accrued_cost(),
+            offline_sim.node.total_accrued_cost(),
+        );
+        trajectory.push((online_sim.t, regret));
+    }
+    trajectory
+}
+
+/// Runs an instance over the access list, recording its cumulative accrued cost
+/// after each tick.
+pub fn cost_trajectory<T: Algorithm>(
+    instance: T,
+    access_list: Vec<u64>,
+    num_ticks: u64,
+) -> Vec<f64> {
+    let mut sim = Simulator::new(access_list, instance);
+    let mut trajectory = Vec::with_capacity(num_ticks as usize);
+    for _ in 0..num_ticks {
+        sim.tick();
+        trajectory.push(sim.node.total_accrued_cost());
+    }
+    trajectory
+}
+
+/// Runs an online and an offline instance in lockstep and computes the competitive
+/// ratio of the costs accrued within each consecutive window of `window` ticks. The
+/// final window may be shorter if `num_ticks` is not a multiple of `window`. Windows in
+/// which the offline instance accrues no cost follow [`crate::competitive_ratio`].
+pub fn windowed_ratios<A: Algorithm, B: Algorithm>(
+    online: A,
+    offline: B,
+    access_list: Vec<u64>,
+    num_ticks: u64,
+    window: u64,
+) -> Vec<f64> {
+    assert!(window > 0, "window must span at least one tick");
+    let mut online_sim = Simulator::new(access_list.clone(), online);
+    let mut offline_sim = Simulator::new(access_list, offline);
+    let mut ratios = Vec::new();
+    let (mut online_start, mut offline_start) = (0.0, 0.0);
+    let mut ticks_in_window = 0;
+    for _ in 0..num_ticks {
+        online_sim.tick();
+        offline_sim.tick();
+        ticks_in_window += 1;
+        if ticks_in_window < window && online_sim.t != num_ticks {
+            continue;
+        }
+        ticks_in_window = 0;
+        let online_cost = online_sim.node.total_accrued_cost();
+        let offline_cost = offline_sim.node.total_accrued_cost();
+        ratios.push(crate::competitive_ratio(
+            online_cost - online_start,
+            offline_cost - offline_start,
+        ));
+        online_start = online_cost;
+        offline_start = offline_cost;
+    }
+    ratios
+}
+
+/// Runs an online and an offline instance in lockstep and writes a CSV to `path` with
+/// one row per tick holding each instance's policy and cumulative cost, along with
+/// the cumulative competitive ratio so far. Ticks before the offline instance accrues
+/// any cost follow [`crate::competitive_ratio`].
+pub fn export_cost_timeseries<A: Algorithm, B: Algorithm, P: AsRef<Path>>(
+    online: A,
+    offline: B,
+    access_list: Vec<u64>,
+    num_ticks: u64,
+    path: P,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(
+        writer,
+        "tick,accessed,online_policy,online_cost,offline_policy,offline_cost,ratio"
+    )?;
+    let mut online_sim = Simulator::new(access_list.clone(), online);
+    let mut offline_sim = Simulator::new(access_list.clone(), offline);
+    for _ in 0..num_ticks {
+        online_sim.tick();
+        offline_sim.tick();
+        let online_cost = online_sim.node.total_accrued_cost();
+        let offline_cost = offline_sim.node.total_accrued_cost();
+        let ratio = crate::competitive_ratio(online_cost, offline_cost);
+        writeln!(
+            writer,
+            "{},{},{:?},{},{:?},{},{}",
+            online_sim.t,
+            access_list.contains(&online_sim.t),
+            online_sim.node.current_policy(),
+            online_cost,
+            offline_sim.node.current_policy(),
+            offline_cost,
+            ratio
+        )?;
+    }
+    writer.flush()
+}
+
+/// Re-evaluates an online algorithm on an existing access list under a different set
+/// of two-tier costs, holding the workload fixed for cost-sensitivity analysis.
+pub fn replay(
+    access_list: &[u64],
+    num_ticks: u64,
+    keep_cost: u64,
+    recover_cost: u64,
+    kind: AlgorithmKind,
+) -> RunSummary {
+    let offline_cost = offline_cost(keep_cost, recover_cost, access_list, num_ticks);
+    let (online_cost, sampled_discard_thresholds) = match kind {
+        AlgorithmKind::Naive => {
+            let sim = run(
+                NaiveInstance::new(keep_cost, recover_cost),
+                access_list,
+                num_ticks,
+            );
+            (sim.node.total_accrued_cost(), Vec::new())
+        }
+        AlgorithmKind::Karlin => {
+            let sim = run(
+                KarlinInstance::new(keep_cost, recover_cost),
+                access_list,
+                num_ticks,
+            );
+            (
+                sim.node.total_accrued_cost(),
+                sim.node.sampled_discard_thresholds().to_vec(),
+            )
+        }
+    };
+    RunSummary {
+        kind,
+        keep_cost,
+        recover_cost,
+        online_cost,
+        offline_cost,
+        competitive_ratio: crate::competitive_ratio(online_cost, offline_cost),
+        sampled_discard_thresholds,
+    }
+}
+
+/// Costs of the offline optimum and of the deterministic and randomized two-tier
+/// algorithms on the same access list, with each online cost's ratio and regret to the
+/// optimum and the worst-case bound from [`Algorithm::competitive_bound`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComparisonReport {
+    pub offline_cost: f64,
+    pub naive_cost: f64,
+    pub naive_ratio: f64,
+    pub naive_regret: i64,
+    pub naive_bound: f64,
+    pub karlin_cost: f64,
+    pub karlin_ratio: f64,
+    pub karlin_regret: i64,
+    pub karlin_bound: f64,
+}
+
+impl ComparisonReport {
+    /// Whether the deterministic ratio exceeds its worst-case bound, which points to a
+    /// bug. The randomized bound only holds in expectation, so a single randomized run
+    /// is not flagged.
+    pub fn exceeds_bound(&self) -> bool {
+        self.naive_ratio > self.naive_bound
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "offline: cost={:.2}", self.offline_cost)?;
+        writeln!(
+            f,
+            "naive: cost={:.2}, ratio={:.2}, regret={}, bound={:.2}",
+            self.naive_cost, self.naive_ratio, self.naive_regret, self.naive_bound
+        )?;
+        writeln!(
+            f,
+            "karlin: cost={:.2}, ratio={:.2}, regret={}, bound={:.2}",
+            self.karlin_cost, self.karlin_ratio, self.karlin_regret, self.karlin_bound
+        )
+    }
+}
+
+/// Runs the offline optimum, the deterministic algorithm and the randomized algorithm
+/// over the same access list for `num_ticks` ticks, computing the offline cost once.
+pub fn compare_all(
+    keep_cost: u64,
+    recover_cost: u64,
+    access_list: &[u64],
+    num_ticks: u64,
+) -> ComparisonReport {
+    let offline_cost = offline_cost(keep_cost, recover_cost, access_list, num_ticks);
+    let naive = run(
+        NaiveInstance::new(keep_cost, recover_cost),
+        access_list,
+        num_ticks,
+    )
+    .node;
+    let karlin = run(
+        KarlinInstance::new(keep_cost, recover_cost),
+        access_list,
+        num_ticks,
+    )
+    .node;
+    ComparisonReport {
+        offline_cost,
+        naive_cost: naive.total_accrued_cost(),
+        naive_ratio: crate::competitive_ratio(naive.total_accrued_cost(), offline_cost),
+        naive_regret: crate::regret(naive.total_accrued_cost(), offline_cost),
+        naive_bound: naive.competitive_bound(),
+        karlin_cost: karlin.total_accrued_cost(),
+        karlin_ratio: crate::competitive_ratio(karlin.total_accrued_cost(), offline_cost),
+        karlin_regret: crate::regret(karlin.total_accrued_cost(), offline_cost),
+        karlin_bound: karlin.competitive_bound(),
+    }
+}
+
+/// Mean competitive ratios of the deterministic and randomized two-tier algorithms
+/// over a grid of costs, indexed as `[keep_cost][recover_cost]`. Displays as a table
+/// with one row per keep cost, one column per recover cost, and each cell showing
+/// the deterministic/randomized mean ratios.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperimentMatrix {
+    pub keep_costs: Vec<u64>,
+    pub recover_costs: Vec<u64>,
+    pub deterministic: Vec<Vec<f64>>,
+    pub randomized: Vec<Vec<f64>>,
+}
+
+impl fmt::Display for ExperimentMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>14}", "keep\\recover")?;
+        for recover_cost in &self.recover_costs {
+            write!(f, " {:>11}", recover_cost)?;
+        }
+        writeln!(f)?;
+        for (i, keep_cost) in self.keep_costs.iter().enumerate() {
+            write!(f, "{:>14}", keep_cost)?;
+            for j in 0..self.recover_costs.len() {
+                let cell = format!(
+                    "{:.2}/{:.2}",
+                    self.deterministic[i][j], self.randomized[i][j]
+                );
+                write!(f, " {:>11}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the deterministic and randomized two-tier algorithms for `trials` random
+/// access lists on every combination of keep and recover cost, seeding all access
+/// lists and sampled thresholds from `seed`.
+pub fn run_experiment_matrix(
+    keep_costs: &[u64],
+    recover_costs: &[u64],
+    trials: usize,
+    seed: u64,
+) -> ExperimentMatrix {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut deterministic = vec![vec![0.0; recover_costs.len()]; keep_costs.len()];
+    let mut randomized = deterministic.clone();
+    for (i, &keep_cost) in keep_costs.iter().enumerate() {
+        for (j, &recover_cost) in recover_costs.iter().enumerate() {
+            for _ in 0..trials {
+                let access_list = generate_exact_access_list(10, 100, &mut rng)
+                    .expect("10 accesses fit in 100 ticks");
+                let num_ticks = *access_list.last().unwrap();
+                deterministic[i][j] += calculate_competitive_ratio(
+                    NaiveInstance::new(keep_cost, recover_cost),
+                    keep_cost,
+                    recover_cost,
+                    &access_list,
+                    num_ticks,
+                );
+                randomized[i][j] += calculate_competitive_ratio(
+                    KarlinInstance::with_rng(keep_cost, recover_cost, &mut rng),
+                    keep_cost,
+                    recover_cost,
+                    access_list,
+                    num_ticks,
+                );
+            }
+            deterministic[i][j] /= trials as f64;
+            randomized[i][j] /= trials as f64;
+        }
+    }
+    ExperimentMatrix {
+        keep_costs: keep_costs.to_vec(),
+        recover_costs: recover_costs.to_vec(),
+        deterministic,
+        randomized,
+    }
+}
+
+/// Summary statistics of competitive ratios over many runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatioStats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 95th percentile, by the nearest-rank method.
+    pub p95: f64,
+    /// Seed of the run that hit `max`, when the runs were seeded, so that run can be
+    /// reproduced with [`rerun_seeded`].
+    pub max_seed: Option<u64>,
+    /// Seed and ratio of every run in run order, when the runs were seeded, so that
+    /// any of them can be reproduced with [`rerun_seeded`]. Empty otherwise.
+    pub seeded_runs: Vec<(u64, f64)>,
+}
+
+impl RatioStats {
+    /// Summarizes a set of ratios.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratios` is empty.
+    pub fn from_ratios(mut ratios: Vec<f64>) -> Self {
+        assert!(
+            !ratios.is_empty(),
+            "at least one ratio is needed for statistics"
+        );
+        ratios.sort_by(f64::total_cmp);
+        let n = ratios.len() as f64;
+        let mean = ratios.iter().sum::<f64>() / n;
+        let variance = ratios
+            .iter()
+            .map(|ratio| (ratio - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let p95_rank = (0.95 * n).ceil() as usize;
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min: ratios[0],
+            max: ratios[ratios.len() - 1],
+            p95: ratios[p95_rank.max(1) - 1],
+            max_seed: None,
+            seeded_runs: vec![],
+        }
+    }
+    /// Summarizes a set of seeded runs, given as `(seed, ratio)` pairs, recording each
+    /// run along with the seed of the run with the highest ratio.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `runs` is empty.
+    pub fn from_seeded_ratios(runs: Vec<(u64, f64)>) -> Self {
+        let max_seed = runs
+            .iter()
+            .copied()
+            .reduce(|worst, run| if run.1 > worst.1 { run } else { worst })
+            .map(|(seed, _)| seed);
+        let ratios = runs.iter().map(|&(_, ratio)| ratio).collect();
+        Self {
+            max_seed,
+            seeded_runs: runs,
+            ..Self::from_ratios(ratios)
+        }
+    }
+}
+
+impl fmt::Display for RatioStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean={:.2}, std_dev={:.2}, min={:.2}, max={:.2}, p95={:.2}",
+            self.mean, self.std_dev, self.min, self.max, self.p95
+        )?;
+        if let Some(seed) = self.max_seed {
+            write!(f, ", max_seed={}", seed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a fresh two-tier instance from `make_instance` over each of `runs` freshly
+/// generated access lists of 10 accesses within 100 ticks, and summarizes the
+/// competitive ratios against the offline optimum.
+///
+/// # Panics
+///
+/// Panics if `runs` is zero.
+pub fn competitive_ratio_stats<T: Algorithm, F: Fn() -> T>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    runs: usize,
+) -> RatioStats {
+    let ratios = (0..runs)
+        .map(|_| {
+            let access_list = generate_access_list(10, 100);
+            let num_ticks = *access_list.last().unwrap();
+            calculate_competitive_ratio(
+                make_instance(),
+                keep_cost,
+                recover_cost,
+                access_list,
+                num_ticks,
+            )
+        })
+        .collect();
+    RatioStats::from_ratios(ratios)
+}
+
+/// Like [`competitive_ratio_stats`], but seeding run `i` with [`run_seed`]`(seed, i)`.
+/// Each run draws its access list from its own rng and then hands that rng to
+/// `make_instance`, so the result is reproducible and does not depend on the order runs
+/// execute in. The seed of the worst run is recorded in [`RatioStats::max_seed`].
+///
+/// # Panics
+///
+/// Panics if `runs` is zero.
+pub fn competitive_ratio_stats_seeded<T: Algorithm, F: Fn(StdRng) -> T>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    runs: usize,
+    seed: u64,
+) -> RatioStats {
+    let runs = (0..runs)
+        .map(|run| {
+            let run_seed = run_seed(seed, run);
+            let ratio = rerun_seeded(&make_instance, keep_cost, recover_cost, run_seed);
+            (run_seed, ratio)
+        })
+        .collect();
+    RatioStats::from_seeded_ratios(runs)
+}
+
+/// Like [`competitive_ratio_stats_seeded`], distributing the runs across threads. It
+/// returns the same statistics as the sequential version for the same seed.
+///
+/// # Panics
+///
+/// Panics if `runs` is zero.
+#[cfg(feature = "rayon")]
+pub fn competitive_ratio_stats_parallel<T: Algorithm, F: Fn(StdRng) -> T + Sync>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    runs: usize,
+    seed: u64,
+) -> RatioStats {
+    use rayon::prelude::*;
+    let runs = (0..runs)
+        .into_par_iter()
+        .map(|run| {
+            let run_seed = run_seed(seed, run);
+            let ratio = rerun_seeded(&make_instance, keep_cost, recover_cost, run_seed);
+            (run_seed, ratio)
+        })
+        .collect();
+    RatioStats::from_seeded_ratios(runs)
+}
+
+/// Mean competitive ratio of the randomized two-tier algorithm for each recover cost in
+/// `recover_range`, over `runs` access lists per cost as in [`competitive_ratio_stats`],
+/// giving the ratio curve as the recover cost grows relative to the keep cost.
+///
+/// # Panics
+///
+/// Panics if `runs` is zero.
+pub fn sweep_recover_cost(
+    keep_cost: u64,
+    recover_range: RangeInclusive<u64>,
+    runs: usize,
+) -> Vec<(u64, f64)> {
+    recover_range
+        .map(|recover_cost| {
+            let stats = competitive_ratio_stats(
+                || KarlinInstance::new(keep_cost, recover_cost),
+                keep_cost,
+                recover_cost,
+                runs,
+            );
+            (recover_cost, stats.mean)
+        })
+        .collect()
+}
+
+/// Like [`sweep_recover_cost`], but running each recover cost as in
+/// [`competitive_ratio_stats_seeded`] with the same seed, so the curve is reproducible
+/// and every cost is measured on the same access lists.
+///
+/// # Panics
+///
+/// Panics if `runs` is zero.
+pub fn sweep_recover_cost_seeded(
+    keep_cost: u64,
+    recover_range: RangeInclusive<u64>,
+    runs: usize,
+    seed: u64,
+) -> Vec<(u64, f64)> {
+    recover_range
+        .map(|recover_cost| {
+            let stats = competitive_ratio_stats_seeded(
+                |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng),
+                keep_cost,
+                recover_cost,
+                runs,
+                seed,
+            );
+            (recover_cost, stats.mean)
+        })
+        .collect()
+}
+
+/// Searches for the access list on which an online two-tier algorithm fares worst,
+/// running a fresh instance from `make_instance` over each of `trials` random lists of
+/// `list_len` accesses within `horizon` ticks. Returns the highest competitive ratio
+/// found along with the list that produced it.
+///
+/// # Panics
+///
+/// Panics if `trials` is zero or `list_len` is zero or exceeds `horizon`.
+pub fn find_worst_case<T: Algorithm, F: Fn() -> T>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    trials: usize,
+    list_len: usize,
+    horizon: u64,
+) -> (f64, Vec<u64>) {
+    find_worst_case_with_rng(
+        make_instance,
+        keep_cost,
+        recover_cost,
+        trials,
+        list_len,
+        horizon,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Like [`find_worst_case`], drawing the access lists from the provided rng so that
+/// the search can be reproduced from a seed.
+///
+/// # Panics
+///
+/// Panics if `trials` is zero or `list_len` is zero or exceeds `horizon`.
+pub fn find_worst_case_with_rng<T: Algorithm, F: Fn() -> T, R: rand::Rng>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    trials: usize,
+    list_len: usize,
+    horizon: u64,
+    rng: &mut R,
+) -> (f64, Vec<u64>) {
+    (0..trials)
+        .map(|_| {
+            let access_list = generate_access_list_with_rng(list_len, horizon, rng);
+            let num_ticks = *access_list.last().unwrap();
+            let ratio = calculate_competitive_ratio(
+                make_instance(),
+                keep_cost,
+                recover_cost,
+                &access_list,
+                num_ticks,
+            );
+            (ratio, access_list)
+        })
+        .reduce(|worst, candidate| {
+            if candidate.0 > worst.0 {
+                candidate
+            } else {
+                worst
+            }
+        })
+        .expect("at least one trial is needed to find a worst case")
+}
+
+/// Estimates the mean competitive ratio of a randomized online two-tier algorithm over
+/// a fixed access list, running fresh instances from `make_instance` until the running
+/// mean moves by less than `tolerance` over the last `window` runs, or until `max_runs`
+/// runs. Returns the estimate along with the number of runs it took.
+///
+/// # Panics
+///
+/// Panics if `window` or `max_runs` is zero.
+pub fn run_until_converged<T: Algorithm, F: Fn() -> T>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    access_list: &[u64],
+    tolerance: f64,
+    window: usize,
+    max_runs: usize,
+) -> (f64, usize) {
+    assert!(window > 0, "convergence needs a window of at least one run");
+    assert!(
+        max_runs > 0,
+        "at least one run is needed to estimate a ratio"
+    );
+    let num_ticks = access_list.last().copied().unwrap_or(0);
+    let mut total = 0.0;
+    let mut means = Vec::with_capacity(max_runs);
+    for runs in 1..=max_runs {
+        total += calculate_competitive_ratio(
+            make_instance(),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        let mean = total / runs as f64;
+        means.push(mean);
+        if runs > window && (mean - means[runs - 1 - window]).abs() < tolerance {
+            return (mean, runs);
+        }
+    }
+    (total / max_runs as f64, max_runs)
+}
+
+/// Seed of run `run` of a seeded experiment with master seed `seed`.
+pub fn run_seed(seed: u64, run: usize) -> u64 {
+    seed.wrapping_add(run as u64)
+}
+
+/// Reruns a single run of [`competitive_ratio_stats_seeded`] from its recorded seed,
+/// such as [`RatioStats::max_seed`], returning the same competitive ratio.
+pub fn rerun_seeded<T: Algorithm, F: Fn(StdRng) -> T>(
+    make_instance: F,
+    keep_cost: u64,
+    recover_cost: u64,
+    seed: u64,
+) -> f64 {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let access_list = generate_access_list_with_rng(10, 100, &mut rng);
+    let num_ticks = *access_list.last().unwrap();
+    calculate_competitive_ratio(
+        make_instance(rng),
+        keep_cost,
+        recover_cost,
+        access_list,
+        num_ticks,
+    )
+}
+
+fn run<T: Algorithm>(instance: T, access_list: &[u64], num_ticks: u64) -> Simulator<T> {
+    let mut sim = Simulator::new(access_list.iter().copied(), instance);
+    sim.run(num_ticks);
+    sim
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::two_tier::{NaiveInstance, OfflineInstance};
+    #[test]
+    fn final_regret_matches_cost_difference() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let num_ticks = 11;
+        let access_list = vec![4, 8, 12];
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        let trajectory = regret_trajectory(
+            online.clone(),
+            offline.clone(),
+            access_list.clone(),
+            num_ticks,
+        );
+        assert_eq!(num_ticks as usize, trajectory.len());
+
+        let mut online_sim = Simulator::new(access_list.clone(), online);
+        let mut offline_sim = Simulator::new(access_list, offline);
+        online_sim.run(num_ticks);
+        offline_sim.run(num_ticks);
+        let expected = online_sim.node.total_accrued_cost() - offline_sim.node.total_accrued_cost();
+        assert_eq!(Some(&(num_ticks, expected as i64)), trajectory.last());
+    }
+    #[test]
+    fn windowed_ratios_piecewise() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        // Frequent accesses for the first window, then sparse accesses that make
+        // the deterministic instance discard too late.
+        let mut access_list: Vec<u64> = (1..=10).map(|i| i * 2).collect();
+        access_list.extend((6..=10).map(|i| i * 4));
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        let ratios = windowed_ratios(online, offline, access_list, 40, 20);
+        assert_eq!(vec![1.0, 5.0 / 3.0], ratios);
+    }
+    #[test]
+    fn replay_with_modified_recover_cost() {
+        let access_list = vec![4, 8, 12];
+        let ratios: Vec<f64> = [2, 3, 5]
+            .into_iter()
+            .map(|recover_cost| {
+                replay(&access_list, 12, 1, recover_cost, AlgorithmKind::Naive).competitive_ratio
+            })
+            .collect();
+        // Once recovering costs more than holding through a whole gap, both the
+        // deterministic and offline instances keep throughout and agree.
+        assert_eq!(vec![3.0 / 2.0, 5.0 / 3.0, 1.0], ratios);
+    }
+    #[test]
+    fn replay_records_sampled_thresholds() {
+        let access_list = vec![4, 8, 12];
+        let summary = replay(&access_list, 12, 1, 3, AlgorithmKind::Karlin);
+        // One initial sample plus one per access.
+        assert_eq!(
+            access_list.len() + 1,
+            summary.sampled_discard_thresholds.len()
+        );
+        assert!(summary.sampled_discard_thresholds.iter().all(|&d| d <= 3.0));
+        let summary = replay(&access_list, 12, 1, 3, AlgorithmKind::Naive);
+        assert!(summary.sampled_discard_thresholds.is_empty());
+    }
+    #[test]
+    fn comparison_ignores_accesses_after_the_last_tick() {
+        // The access at tick 12 falls outside the run, so the offline discards for free
+        // over the last two ticks, as it does past the last access of any run.
+        let report = compare_all(1, 3, &[4, 8, 12], 10);
+        assert_eq!(6.0, report.offline_cost);
+        let summary = replay(&[4, 8, 12], 10, 1, 3, AlgorithmKind::Naive);
+        assert_eq!(6.0, summary.offline_cost);
+    }
+    #[test]
+    fn offline_is_cheapest_in_comparison() {
+        let access_list = vec![3, 4, 10, 17, 18, 40];
+        let report = compare_all(1, 5, &access_list, 40);
+        assert!(report.offline_cost <= report.naive_cost);
+        assert!(report.offline_cost <= report.karlin_cost);
+        assert_eq!(report.naive_cost / report.offline_cost, report.naive_ratio);
+        assert_eq!(
+            (report.naive_cost - report.offline_cost) as i64,
+            report.naive_regret
+        );
+        assert!(report.karlin_regret >= 0);
+        assert_eq!(
+            replay(&access_list, 40, 1, 5, AlgorithmKind::Naive).competitive_ratio,
+            report.naive_ratio
+        );
+        assert_eq!(3, report.to_string().lines().count());
+        assert_eq!(2.0, report.naive_bound);
+        assert!(report.karlin_bound < report.naive_bound);
+        assert!(!report.exceeds_bound());
+    }
+    #[test]
+    fn converges_near_e_over_e_minus_one() {
+        use rand::rngs::StdRng;
+        // Idle gaps as long as the recover cost approach the continuous worst case
+        // e / (e - 1) as the recover cost grows.
+        let recover_cost = 100;
+        let access_list: Vec<u64> = (1..=20).map(|i| i * (recover_cost + 1)).collect();
+        let run = std::cell::Cell::new(0);
+        let make_instance = || {
+            run.set(run.get() + 1);
+            KarlinInstance::with_rng(1, recover_cost, StdRng::seed_from_u64(run.get()))
+        };
+        let (ratio, runs) = run_until_converged(
+            make_instance,
+            1,
+            recover_cost,
+            &access_list,
+            1e-3,
+            50,
+            5_000,
+        );
+        assert!(runs < 5_000, "did not converge");
+        let e = std::f64::consts::E;
+        assert!((ratio - e / (e - 1.0)).abs() < 0.03, "ratio {}", ratio);
+    }
+    #[test]
+    fn experiment_matrix_dimensions() {
+        let matrix = run_experiment_matrix(&[1, 2], &[2, 3, 5], 20, 42);
+        for cells in [&matrix.deterministic, &matrix.randomized] {
+            assert_eq!(2, cells.len());
+            assert!(cells.iter().all(|row| row.len() == 3));
+            assert!(cells.iter().flatten().all(|ratio| ratio.is_finite()));
+        }
+        assert_eq!(matrix, run_experiment_matrix(&[1, 2], &[2, 3, 5], 20, 42));
+        // A header line plus one line per keep cost.
+        assert_eq!(3, matrix.to_string().lines().count());
+    }
+    #[test]
+    fn export_cost_timeseries_rows() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let access_list = vec![2, 4, 10, 20, 21, 30];
+        let num_ticks = 30;
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        let path =
+            std::env::temp_dir().join(format!("export_cost_timeseries_{}.csv", std::process::id()));
+        export_cost_timeseries(online, offline, access_list.clone(), num_ticks, &path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(num_ticks as usize, rows.len());
+        let final_ratio: f64 = rows
+            .last()
+            .unwrap()
+            .rsplit(',')
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        let expected =
+            calculate_competitive_ratio(online, keep_cost, recover_cost, access_list, num_ticks);
+        assert_eq!(expected, final_ratio);
+    }
+    #[test]
+    fn randomized_mean_ratio_below_two() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let stats = competitive_ratio_stats_seeded(
+            |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng),
+            keep_cost,
+            recover_cost,
+            1000,
+            0,
+        );
+        assert!(stats.mean < 2.0, "{}", stats);
+        assert!(stats.min <= stats.mean && stats.mean <= stats.max);
+        assert!(stats.p95 <= stats.max);
+    }
+    #[test]
+    fn recover_cost_sweep_has_one_ratio_per_cost() {
+        let curve = sweep_recover_cost_seeded(1, 2..=12, 50, 0);
+        assert_eq!(
+            (2..=12).collect::<Vec<_>>(),
+            curve.iter().map(|&(c, _)| c).collect::<Vec<_>>()
+        );
+        assert!(curve
+            .iter()
+            .all(|&(_, ratio)| ratio.is_finite() && ratio >= 1.0));
+    }
+    #[test]
+    fn seeded_stats_are_reproducible() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let make_instance = |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let first = competitive_ratio_stats_seeded(make_instance, keep_cost, recover_cost, 200, 7);
+        let second = competitive_ratio_stats_seeded(make_instance, keep_cost, recover_cost, 200, 7);
+        assert_eq!(first, second);
+    }
+    #[test]
+    fn recorded_seed_reproduces_the_worst_run() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let make_instance = |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let stats = competitive_ratio_stats_seeded(make_instance, keep_cost, recover_cost, 200, 7);
+        let seed = stats.max_seed.unwrap();
+        assert!((7..207).contains(&seed));
+        assert_eq!(
+            stats.max,
+            rerun_seeded(make_instance, keep_cost, recover_cost, seed)
+        );
+        assert_eq!(None, RatioStats::from_ratios(vec![1.0, 2.0]).max_seed);
+    }
+    #[test]
+    fn recorded_seeds_reproduce_every_run() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let make_instance = |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let stats = competitive_ratio_stats_seeded(make_instance, keep_cost, recover_cost, 50, 7);
+        assert_eq!(50, stats.seeded_runs.len());
+        for (run, &(seed, ratio)) in stats.seeded_runs.iter().enumerate() {
+            assert_eq!(run_seed(7, run), seed);
+            assert_eq!(
+                ratio,
+                rerun_seeded(make_instance, keep_cost, recover_cost, seed)
+            );
+        }
+        assert!(RatioStats::from_ratios(vec![1.0, 2.0])
+            .seeded_runs
+            .is_empty());
+    }
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_stats_match_sequential() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let make_instance = |rng| KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let sequential =
+            competitive_ratio_stats_seeded(make_instance, keep_cost, recover_cost, 1000, 7);
+        let parallel =
+            competitive_ratio_stats_parallel(make_instance, keep_cost, recover_cost, 1000, 7);
+        assert_eq!(sequential, parallel);
+    }
+    #[test]
+    fn deterministic_worst_case_is_within_bound() {
+        let (keep_cost, recover_cost) = (1, 3);
+        let (ratio, access_list) = find_worst_case_with_rng(
+            || NaiveInstance::new(keep_cost, recover_cost),
+            keep_cost,
+            recover_cost,
+            500,
+            10,
+            100,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert!(ratio > 1.0 && ratio <= 2.0, "ratio {}", ratio);
+        let num_ticks = *access_list.last().unwrap();
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        assert_eq!(
+            ratio,
+            calculate_competitive_ratio(online, keep_cost, recover_cost, access_list, num_ticks)
+        );
+    }
+    #[test]
+    fn ratio_stats_summarize() {
+        let stats = RatioStats::from_ratios((1..=20).map(f64::from).collect());
+        assert_eq!(10.5, stats.mean);
+        assert_eq!(1.0, stats.min);
+        assert_eq!(20.0, stats.max);
+        assert_eq!(19.0, stats.p95);
+    }
+}
@@ -0,0 +1,260 @@
+//! Two-tier problem for storage that never discards, where the only choice is between
+//! keeping the object as is and keeping it compressed. Unlike discarding, compressing
+//! still costs something to hold, so this is the N-tier problem with a bottom tier
+//! that has a time cost, reported as `Compress` rather than `Discard`.
+use crate::n_tier::{self, Tier, TierError};
+use crate::{Algorithm, CostBreakdown, Policy};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::iter::Peekable;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Costs {
+    pub keep_time_cost: f64,
+    pub compressed_time_cost: f64,
+    pub recover_from_compressed_cost: f64,
+}
+
+impl Costs {
+    /// The keep and compress tiers as an N-tier hierarchy.
+    pub fn tiers(&self) -> Vec<Tier> {
+        vec![
+            Tier {
+                time_cost: self.keep_time_cost,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: self.compressed_time_cost,
+                recover_cost: self.recover_from_compressed_cost,
+            },
+        ]
+    }
+}
+
+/// The bottom of the two tiers holds the object compressed, so it is never discarded.
+fn compress_policy(policy: Policy) -> Policy {
+    match policy {
+        Policy::Discard => Policy::Compress,
+        policy => policy,
+    }
+}
+
+/// Deterministic algorithm that compresses once the keep cost paid since the last
+/// access reaches the cost of recovering from compression.
+#[derive(Debug, Clone)]
+pub struct NaiveInstance {
+    inner: n_tier::NaiveInstance,
+}
+
+impl NaiveInstance {
+    pub fn new(costs: Costs) -> Result<Self, TierError> {
+        Ok(Self {
+            inner: n_tier::NaiveInstance::new(costs.tiers())?,
+        })
+    }
+}
+
+impl Algorithm for NaiveInstance {
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        compress_policy(self.inner.current_policy())
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+}
+
+/// Randomized algorithm that compresses once the keep cost paid since the last access
+/// reaches a Karlin threshold, resampled after every access.
+#[derive(Debug, Clone)]
+pub struct KarlinInstance<R: Rng = ThreadRng> {
+    inner: n_tier::KarlinInstance<R>,
+}
+
+impl KarlinInstance {
+    pub fn new(costs: Costs) -> Result<KarlinInstance, TierError> {
+        Self::with_rng(costs, thread_rng())
+    }
+}
+
+impl<R: Rng> KarlinInstance<R> {
+    /// Creates an instance that samples its thresholds from the provided rng, so that
+    /// runs can be reproduced from a seed.
+    pub fn with_rng(costs: Costs, rng: R) -> Result<Self, TierError> {
+        Ok(Self {
+            inner: n_tier::KarlinInstance::with_rng(costs.tiers(), rng)?,
+        })
+    }
+    /// Keep cost paid since the last access at which the object is compressed, as
+    /// sampled after that access.
+    pub fn current_compress_threshold(&self) -> f64 {
+        self.inner.thresholds()[1]
+    }
+}
+
+impl<R: Rng> Algorithm for KarlinInstance<R> {
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        compress_policy(self.inner.current_policy())
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+}
+
+/// Offline algorithm that compresses right after an access whenever holding the object
+/// compressed until the next access and recovering it is cheaper than keeping it.
+#[derive(Debug, Clone)]
+pub struct OfflineInstance<T>
+where
+    T: Iterator<Item = u64>,
+{
+    inner: n_tier::OfflineInstance<T>,
+}
+
+impl<T> OfflineInstance<T>
+where
+    T: Iterator<Item = u64> + Clone,
+{
+    pub fn new(costs: Costs, access_list: Peekable<T>) -> Result<Self, TierError> {
+        Ok(Self {
+            inner: n_tier::OfflineInstance::new(costs.tiers(), access_list)?,
+        })
+    }
+}
+
+impl<T> Algorithm for OfflineInstance<T>
+where
+    T: Iterator<Item = u64> + Clone,
+{
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        compress_policy(self.inner.current_policy())
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        1.0
+    }
+}
+
+pub fn calculate_competitive_ratio<T: Algorithm, L: AsRef<[u64]>>(
+    instance: T,
+    costs: Costs,
+    access_list: L,
+    num_ticks: u64,
+) -> Result<f64, TierError> {
+    let access_list = access_list.as_ref();
+    let offline_cost = n_tier::offline_cost(costs.tiers(), access_list, num_ticks)?;
+    Ok(crate::online_ratio(
+        instance,
+        access_list,
+        num_ticks,
+        offline_cost,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn costs() -> Costs {
+        Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.25,
+            recover_from_compressed_cost: 2.0,
+        }
+    }
+    #[test]
+    fn sparse_accesses_only_pay_keep_compress_and_recover_costs() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let access_list = vec![10, 40];
+        let instances: Vec<Box<dyn Algorithm>> = vec![
+            Box::new(NaiveInstance::new(costs()).unwrap()),
+            Box::new(KarlinInstance::with_rng(costs(), StdRng::seed_from_u64(3)).unwrap()),
+            Box::new(
+                OfflineInstance::new(costs(), access_list.clone().into_iter().peekable()).unwrap(),
+            ),
+        ];
+        for mut instance in instances {
+            for t in 1..=40 {
+                let access = access_list.contains(&t);
+                instance.tick(access);
+                assert_ne!(Policy::Discard, instance.current_policy());
+                let cost = instance.last_tick_cost();
+                if access {
+                    assert!([0.0, 2.0].contains(&cost), "tick {} cost {}", t, cost);
+                } else {
+                    assert!([1.0, 0.25].contains(&cost), "tick {} cost {}", t, cost);
+                }
+            }
+            let breakdown = instance.cost_breakdown();
+            assert_eq!(
+                2.0 * breakdown.num_recoveries as f64,
+                breakdown.recovery_cost
+            );
+        }
+
+        // Compressing once a keep cost of 2 is paid costs 1 + 8 * 0.25 + 2 over the
+        // first gap and 1 + 28 * 0.25 + 2 over the second.
+        let naive = NaiveInstance::new(costs()).unwrap();
+        let mut sim = crate::Simulator::new(access_list.clone(), naive);
+        sim.run(40);
+        assert_eq!(15.0, sim.node.total_accrued_cost());
+        // The optimum compresses right after each access instead.
+        let ratio = calculate_competitive_ratio(
+            NaiveInstance::new(costs()).unwrap(),
+            costs(),
+            access_list,
+            40,
+        )
+        .unwrap();
+        assert_eq!(15.0 / 13.5, ratio);
+    }
+    #[test]
+    fn compressing_cannot_cost_more_to_hold() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 2.0,
+            recover_from_compressed_cost: 2.0,
+        };
+        assert_eq!(
+            Some(TierError::Unordered { index: 1 }),
+            NaiveInstance::new(costs).err()
+        );
+    }
+}
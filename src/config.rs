@@ -0,0 +1,147 @@
+//! Experiment parameters, which can be loaded from a TOML file with the `config`
+//! feature so that experiments can be shared and reproduced.
+use crate::util::AccessGeneration;
+#[cfg(feature = "config")]
+use std::fmt;
+#[cfg(feature = "config")]
+use std::path::Path;
+
+/// Parameters of an experiment comparing the online algorithms against the offline
+/// optimum. Fields missing from a config file take their default values, which match
+/// the defaults of the command line flags.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ExperimentConfig {
+    pub keep_cost: u64,
+    pub recover_cost: u64,
+    /// Number of access lists to run.
+    pub runs: usize,
+    /// Seed for the access lists and sampled thresholds, or fresh entropy if unset.
+    pub seed: Option<u64>,
+    pub access: AccessGeneration,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            keep_cost: 1,
+            recover_cost: 3,
+            runs: 100,
+            seed: None,
+            access: AccessGeneration::default(),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Io(std::io::Error),
+    /// The config file is not a valid experiment config.
+    Parse(toml::de::Error),
+    /// The Poisson access rate is not positive and finite.
+    NonPositiveRate(f64),
+}
+
+#[cfg(feature = "config")]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "invalid config: {}", err),
+            ConfigError::NonPositiveRate(rate) => {
+                write!(
+                    f,
+                    "invalid config: poisson rate must be positive and finite, got {}",
+                    rate
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl std::error::Error for ConfigError {}
+
+/// Parses an experiment config from TOML, rejecting a Poisson rate that is not positive.
+#[cfg(feature = "config")]
+pub fn parse(toml: &str) -> Result<ExperimentConfig, ConfigError> {
+    let config: ExperimentConfig = toml::from_str(toml).map_err(ConfigError::Parse)?;
+    if let AccessGeneration::Poisson { rate, .. } = config.access {
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(ConfigError::NonPositiveRate(rate));
+        }
+    }
+    Ok(config)
+}
+
+/// Reads and parses an experiment config from a TOML file.
+#[cfg(feature = "config")]
+pub fn load<P: AsRef<Path>>(path: P) -> Result<ExperimentConfig, ConfigError> {
+    let toml = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+    parse(&toml)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn default_generates_ten_accesses() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let config = ExperimentConfig::default();
+        let access_list = config
+            .access
+            .generate(&mut StdRng::seed_from_u64(7))
+            .unwrap();
+        assert_eq!(10, access_list.len());
+        assert!(access_list.iter().all(|&t| (1..=100).contains(&t)));
+    }
+    #[cfg(feature = "config")]
+    #[test]
+    fn parses_sample_config() {
+        let config = parse(
+            r#"
+            recover_cost = 5
+            runs = 20
+            seed = 42
+
+            [access]
+            method = "poisson"
+            rate = 0.1
+            horizon = 500
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            ExperimentConfig {
+                keep_cost: 1,
+                recover_cost: 5,
+                runs: 20,
+                seed: Some(42),
+                access: AccessGeneration::Poisson {
+                    rate: 0.1,
+                    horizon: 500
+                },
+            },
+            config
+        );
+        assert!(matches!(parse("runs = -1"), Err(ConfigError::Parse(_))));
+        for rate in ["0.0", "-0.5", "nan", "inf"] {
+            let toml = format!(
+                "[access]\nmethod = \"poisson\"\nrate = {}\nhorizon = 500",
+                rate
+            );
+            assert!(matches!(parse(&toml), Err(ConfigError::NonPositiveRate(_))));
+        }
+        assert!(matches!(
+            load("does/not/exist.toml"),
+            Err(ConfigError::Io(_))
+        ));
+    }
+}
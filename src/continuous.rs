@@ -0,0 +1,289 @@
+//! Two-tier problem in continuous time. Accesses happen at real-valued times and costs
+//! are rates per unit of time, so the simulation advances from one access to the next
+//! instead of tick by tick, and thresholds are not rounded to integers.
+use crate::karlin;
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+
+/// An online or offline algorithm that is told how much time passed since the previous
+/// event, rather than ticked once per unit of time.
+pub trait ContinuousAlgorithm {
+    /// Holds the object for `duration`, then recovers it if the object is accessed at
+    /// the end of that time.
+    fn elapse(&mut self, duration: f64, access: bool);
+    fn total_accrued_cost(&self) -> f64;
+}
+
+/// Drives a continuous-time algorithm through an access list by jumping from one access
+/// to the next.
+#[derive(Debug, Clone)]
+pub struct ContinuousSimulator<T> {
+    pub t: f64,
+    access: Vec<f64>,
+    next_access: usize,
+    pub node: T,
+}
+
+impl<T: ContinuousAlgorithm> ContinuousSimulator<T> {
+    pub fn new(mut access: Vec<f64>, node: T) -> Self {
+        access.sort_by(f64::total_cmp);
+        Self {
+            t: 0.0,
+            access,
+            next_access: 0,
+            node,
+        }
+    }
+    /// Advances to time `end`, processing every access up to and including it.
+    /// Accesses behind the clock are skipped.
+    pub fn run_until(&mut self, end: f64) {
+        while let Some(&at) = self.access.get(self.next_access) {
+            if at > end {
+                break;
+            }
+            self.next_access += 1;
+            if at < self.t {
+                continue;
+            }
+            self.node.elapse(at - self.t, true);
+            self.t = at;
+        }
+        if end > self.t {
+            self.node.elapse(end - self.t, false);
+            self.t = end;
+        }
+    }
+    /// Advances to the last access.
+    pub fn run_to_completion(&mut self) {
+        if let Some(&last) = self.access.last() {
+            self.run_until(last);
+        }
+    }
+}
+
+/// Costs accrued by an online algorithm that keeps the object until the keep cost paid
+/// since the last access reaches a threshold, then discards it.
+#[derive(Debug, Clone)]
+struct ThresholdState {
+    keep_cost: f64,
+    recover_cost: f64,
+    idle: f64,
+    accrued_cost: f64,
+}
+
+impl ThresholdState {
+    fn new(keep_cost: f64, recover_cost: f64) -> Self {
+        Self {
+            keep_cost,
+            recover_cost,
+            idle: 0.0,
+            accrued_cost: 0.0,
+        }
+    }
+    /// Holds the object for `duration` given the current discard threshold, returning
+    /// whether the object was accessed.
+    fn elapse(&mut self, duration: f64, access: bool, threshold: f64) -> bool {
+        // Time since the last access at which the object is discarded. Keeping for free
+        // never pays off discarding.
+        let discard_at = if self.keep_cost > 0.0 {
+            threshold / self.keep_cost
+        } else {
+            f64::INFINITY
+        };
+        let kept = (self.idle + duration).min(discard_at) - self.idle.min(discard_at);
+        self.accrued_cost += self.keep_cost * kept;
+        self.idle += duration;
+        if !access {
+            return false;
+        }
+        // Incur a recovery cost if necessary.
+        if self.idle >= discard_at {
+            self.accrued_cost += self.recover_cost;
+        }
+        self.idle = 0.0;
+        true
+    }
+}
+
+/// Deterministic 2-competitive algorithm: discards once the keep cost paid since the
+/// last access reaches the recover cost.
+#[derive(Debug, Clone)]
+pub struct NaiveInstance {
+    state: ThresholdState,
+}
+
+impl NaiveInstance {
+    pub fn new(keep_cost: f64, recover_cost: f64) -> Self {
+        Self {
+            state: ThresholdState::new(keep_cost, recover_cost),
+        }
+    }
+}
+
+impl ContinuousAlgorithm for NaiveInstance {
+    fn elapse(&mut self, duration: f64, access: bool) {
+        let threshold = self.state.recover_cost;
+        self.state.elapse(duration, access, threshold);
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.state.accrued_cost
+    }
+}
+
+/// Randomized algorithm that discards once the keep cost paid since the last access
+/// reaches a threshold sampled from the continuous karlin density after every access.
+#[derive(Debug, Clone)]
+pub struct KarlinInstance<R: Rng = ThreadRng> {
+    state: ThresholdState,
+    threshold: f64,
+    rng: R,
+}
+
+impl KarlinInstance {
+    pub fn new(keep_cost: f64, recover_cost: f64) -> Self {
+        Self::with_rng(keep_cost, recover_cost, thread_rng())
+    }
+}
+
+impl<R: Rng> KarlinInstance<R> {
+    /// Creates an instance that samples its thresholds from the provided rng, so that
+    /// runs can be reproduced from a seed.
+    pub fn with_rng(keep_cost: f64, recover_cost: f64, mut rng: R) -> Self {
+        Self {
+            threshold: karlin::sample_continuous_with_rng(recover_cost, &mut rng),
+            state: ThresholdState::new(keep_cost, recover_cost),
+            rng,
+        }
+    }
+}
+
+impl<R: Rng> ContinuousAlgorithm for KarlinInstance<R> {
+    fn elapse(&mut self, duration: f64, access: bool) {
+        if self.state.elapse(duration, access, self.threshold) {
+            self.threshold =
+                karlin::sample_continuous_with_rng(self.state.recover_cost, &mut self.rng);
+        }
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.state.accrued_cost
+    }
+}
+
+/// Omniscient algorithm: over each gap between accesses it either keeps the object
+/// throughout or discards it right away, whichever is cheaper. Time after the last
+/// access is free, as the object is discarded immediately.
+#[derive(Debug, Clone)]
+pub struct OfflineInstance {
+    keep_cost: f64,
+    recover_cost: f64,
+    idle: f64,
+    accrued_cost: f64,
+}
+
+impl OfflineInstance {
+    pub fn new(keep_cost: f64, recover_cost: f64) -> Self {
+        Self {
+            keep_cost,
+            recover_cost,
+            idle: 0.0,
+            accrued_cost: 0.0,
+        }
+    }
+}
+
+impl ContinuousAlgorithm for OfflineInstance {
+    fn elapse(&mut self, duration: f64, access: bool) {
+        self.idle += duration;
+        if access {
+            self.accrued_cost += (self.keep_cost * self.idle).min(self.recover_cost);
+            self.idle = 0.0;
+        }
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+}
+
+pub fn calculate_competitive_ratio<T: ContinuousAlgorithm>(
+    instance: T,
+    keep_cost: f64,
+    recover_cost: f64,
+    access_list: Vec<f64>,
+) -> f64 {
+    // Offline, omniscient instance.
+    let offline = OfflineInstance::new(keep_cost, recover_cost);
+    let mut sim = ContinuousSimulator::new(access_list.clone(), offline);
+    sim.run_to_completion();
+    let offline_cost = sim.node.total_accrued_cost();
+
+    // Online instance.
+    let mut sim = ContinuousSimulator::new(access_list, instance);
+    sim.run_to_completion();
+    let online_cost = sim.node.total_accrued_cost();
+
+    // Competitive ratio.
+    crate::competitive_ratio(online_cost, offline_cost)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{two_tier, Algorithm, Simulator};
+    #[test]
+    fn discrete_cost_converges_as_ticks_shrink() {
+        let access_times = vec![2.3, 5.75, 9.1];
+        let mut sim = ContinuousSimulator::new(access_times.clone(), NaiveInstance::new(1.0, 3.0));
+        sim.run_to_completion();
+        // Gaps of 2.3 are kept, while gaps of 3.45 and 3.35 are kept for 3 and then
+        // recovered for 3.
+        let continuous_cost = sim.node.total_accrued_cost();
+        assert!((continuous_cost - 14.3).abs() < 1e-9);
+
+        // With n ticks per unit of time, keeping costs 1 per tick and recovering 3n,
+        // and costs are scaled back by n.
+        let mut errors = vec![];
+        for ticks_per_unit in [1u64, 10, 100, 1_000] {
+            let access_list: Vec<u64> = access_times
+                .iter()
+                .map(|t| (t * ticks_per_unit as f64).round() as u64)
+                .collect();
+            let num_ticks = *access_list.last().unwrap();
+            let mut sim = Simulator::new(
+                access_list,
+                two_tier::NaiveInstance::new(1, 3 * ticks_per_unit),
+            );
+            sim.run(num_ticks);
+            let discrete_cost = sim.node.total_accrued_cost() / ticks_per_unit as f64;
+            errors.push((discrete_cost - continuous_cost).abs());
+        }
+        assert!(errors.windows(2).all(|w| w[1] <= w[0]));
+        assert!(errors[3] < 0.01);
+    }
+    #[test]
+    fn karlin_matches_e_over_e_minus_one_on_worst_case_gaps() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        // Gaps where keeping costs exactly the recover cost are the worst case, on
+        // which the expected ratio is exactly e / (e - 1).
+        let (keep_cost, recover_cost) = (2.0, 5.0);
+        let access_list: Vec<f64> = (1..=100_000).map(|i| i as f64 * 2.5).collect();
+        let rng = StdRng::seed_from_u64(0);
+        let online = KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let ratio = calculate_competitive_ratio(online, keep_cost, recover_cost, access_list);
+        let e = std::f64::consts::E;
+        assert!((ratio - e / (e - 1.0)).abs() < 0.01, "ratio {}", ratio);
+    }
+    #[test]
+    fn run_until_splits_gaps() {
+        let mut whole = ContinuousSimulator::new(vec![4.0, 10.0], NaiveInstance::new(1.0, 3.0));
+        whole.run_until(12.0);
+        let mut split = ContinuousSimulator::new(vec![4.0, 10.0], NaiveInstance::new(1.0, 3.0));
+        for end in [1.0, 2.5, 7.0, 12.0] {
+            split.run_until(end);
+        }
+        assert_eq!(12.0, split.t);
+        // Both gaps are kept for 3 and recovered for 3, and the trailing 2 is kept.
+        assert_eq!(14.0, whole.node.total_accrued_cost());
+        assert_eq!(14.0, split.node.total_accrued_cost());
+    }
+}
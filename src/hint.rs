@@ -0,0 +1,232 @@
+//! Partial knowledge of future accesses, spanning the range from a purely online
+//! algorithm (no hint) to the offline optimum (a perfect hint).
+use crate::{karlin, Algorithm, CostBreakdown, Policy};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+
+pub trait Hint {
+    /// Estimated tick of the next access at or after `current_tick`, if known.
+    fn next_access_estimate(&self, current_tick: u64) -> Option<u64>;
+}
+
+/// Knows nothing about future accesses.
+#[derive(Debug, Clone, Copy)]
+pub struct NoHint;
+
+impl Hint for NoHint {
+    fn next_access_estimate(&self, _current_tick: u64) -> Option<u64> {
+        None
+    }
+}
+
+/// Knows exactly when the next access happens.
+#[derive(Debug, Clone)]
+pub struct PerfectHint {
+    access_list: Vec<u64>,
+}
+
+impl PerfectHint {
+    pub fn new(access_list: Vec<u64>) -> Self {
+        Self { access_list }
+    }
+}
+
+impl Hint for PerfectHint {
+    fn next_access_estimate(&self, current_tick: u64) -> Option<u64> {
+        let idx = self.access_list.partition_point(|&t| t < current_tick);
+        self.access_list.get(idx).copied()
+    }
+}
+
+/// Knows when each access happens up to gaussian noise, drawn once per access so
+/// that repeated queries about the same access agree.
+#[derive(Debug, Clone)]
+pub struct NoisyHint {
+    access_list: Vec<u64>,
+    estimates: Vec<u64>,
+}
+
+impl NoisyHint {
+    pub fn new<R: Rng>(access_list: Vec<u64>, std_dev: f64, rng: &mut R) -> Self {
+        let estimates = access_list
+            .iter()
+            .map(|&t| {
+                // Box-Muller transform of two uniforms into a standard normal.
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                (t as f64 + std_dev * z).round().max(0.0) as u64
+            })
+            .collect();
+        Self {
+            access_list,
+            estimates,
+        }
+    }
+}
+
+impl Hint for NoisyHint {
+    fn next_access_estimate(&self, current_tick: u64) -> Option<u64> {
+        let idx = self.access_list.partition_point(|&t| t < current_tick);
+        self.estimates.get(idx).copied()
+    }
+}
+
+/// Two-tier algorithm that consults a hint every tick. When the hint estimates the
+/// next access, it discards if keeping until then would cost at least the recovery
+/// cost, as the offline does. Without an estimate it falls back to a sampled Karlin
+/// threshold.
+#[derive(Debug, Clone)]
+pub struct HintedInstance<H: Hint, R: Rng = ThreadRng> {
+    t: u64,
+    keep_cost: u64,
+    recover_cost: u64,
+    policy: Policy,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+    last_access: u64,
+    t_to_wait_before_discard: u64,
+    hint: H,
+    rng: R,
+}
+
+impl<H: Hint> HintedInstance<H> {
+    pub fn new(keep_cost: u64, recover_cost: u64, hint: H) -> Self {
+        Self::with_rng(keep_cost, recover_cost, hint, thread_rng())
+    }
+}
+
+impl<H: Hint, R: Rng> HintedInstance<H, R> {
+    pub fn with_rng(keep_cost: u64, recover_cost: u64, hint: H, mut rng: R) -> Self {
+        Self {
+            t: 0,
+            last_access: 0,
+            keep_cost,
+            recover_cost,
+            policy: Policy::Keep,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+            t_to_wait_before_discard: karlin::sample_with_rng(recover_cost, &mut rng),
+            hint,
+            rng,
+        }
+    }
+}
+
+impl<H: Hint, R: Rng> Algorithm for HintedInstance<H, R> {
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        // Only idle ticks discard. Without an estimate, they compare the keep cost paid
+        // before them against the sampled threshold.
+        if !access {
+            let should_discard = match self.hint.next_access_estimate(self.t) {
+                Some(next_access) => {
+                    self.keep_cost
+                        .saturating_mul(next_access.saturating_sub(self.t))
+                        >= self.recover_cost
+                }
+                None => {
+                    self.keep_cost
+                        .saturating_mul(self.t.saturating_sub(self.last_access + 1))
+                        >= self.t_to_wait_before_discard
+                }
+            };
+            if matches!(self.policy, Policy::Keep) && should_discard {
+                log_transition!(
+                    self.t,
+                    self.policy,
+                    Policy::Discard,
+                    "discard: next access estimated at {:?}",
+                    self.hint.next_access_estimate(self.t)
+                );
+                self.policy = Policy::Discard;
+                self.breakdown.num_discards += 1;
+            }
+            if matches!(self.policy, Policy::Keep) {
+                self.accrued_cost += self.keep_cost as f64;
+                self.breakdown.time_cost += self.keep_cost as f64;
+            }
+            return;
+        }
+        self.t_to_wait_before_discard = karlin::sample_with_rng(self.recover_cost, &mut self.rng);
+        self.last_access = self.t;
+
+        // Incur a recovery cost if necessary.
+        if matches!(self.policy, Policy::Discard) {
+            self.accrued_cost += self.recover_cost as f64;
+            self.breakdown.recovery_cost += self.recover_cost as f64;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
+            self.policy = Policy::Keep;
+        }
+    }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.last_access = 0;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+        self.policy = Policy::Keep;
+        self.t_to_wait_before_discard = karlin::sample_with_rng(self.recover_cost, &mut self.rng);
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::two_tier::calculate_competitive_ratio;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    #[test]
+    fn perfect_hint_matches_offline() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let online = HintedInstance::with_rng(
+                keep_cost,
+                recover_cost,
+                PerfectHint::new(access_list.clone()),
+                StdRng::seed_from_u64(rng.gen()),
+            );
+            let ratio = calculate_competitive_ratio(
+                online,
+                keep_cost,
+                recover_cost,
+                access_list,
+                num_ticks,
+            );
+            assert_eq!(1.0, ratio);
+        }
+    }
+    #[test]
+    fn noisy_and_missing_hints() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let access_list = vec![10, 20, 30];
+        let hint = NoisyHint::new(access_list.clone(), 2.0, &mut rng);
+        assert_eq!(hint.next_access_estimate(11), hint.next_access_estimate(20));
+        assert_eq!(None, hint.next_access_estimate(31));
+        assert_eq!(None, NoHint.next_access_estimate(1));
+        assert_eq!(
+            Some(20),
+            PerfectHint::new(access_list).next_access_estimate(11)
+        );
+    }
+}
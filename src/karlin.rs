@@ -1,4 +1,5 @@
 use rand::{thread_rng, Rng};
+use std::cell::Cell;
 
 #[cfg(test)]
 mod test {
@@ -9,9 +10,197 @@ mod test {
         let res = pdf(cost, cost);
         assert_eq!(format!("{:.2}", res), "1.58");
     }
+    #[test]
+    fn sample_frequencies_match_pdf() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let cost = 3;
+        let n = 100_000;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = vec![0usize; cost as usize + 1];
+        for _ in 0..n {
+            counts[sample_with_rng(cost, &mut rng) as usize] += 1;
+        }
+        for t in 0..=cost {
+            let expected = normalized_pdf(t, cost);
+            let observed = counts[t as usize] as f64 / n as f64;
+            assert!((expected - observed).abs() < 0.01);
+        }
+    }
+    #[test]
+    fn sample_mean_matches_continuous_expectation() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let cost = 1000;
+        let n = 100_000;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mean = (0..n)
+            .map(|_| sample_with_rng(cost, &mut rng) as f64)
+            .sum::<f64>()
+            / n as f64;
+        let expected = cost as f64 / (std::f64::consts::E - 1.0);
+        assert!((mean - expected).abs() / expected < 0.02);
+    }
+    #[test]
+    fn moments_match_numerical_integration() {
+        let c = 3u64;
+        let e = std::f64::consts::E;
+        let density = |t: f64| (t / c as f64).exp() / ((e - 1.0) * c as f64);
+        // Midpoint rule over [0, C].
+        let steps = 100_000;
+        let dt = c as f64 / steps as f64;
+        let (mut mean, mut second_moment) = (0.0, 0.0);
+        for i in 0..steps {
+            let t = (i as f64 + 0.5) * dt;
+            mean += t * density(t) * dt;
+            second_moment += t * t * density(t) * dt;
+        }
+        assert_eq!(format!("{:.4}", mean), format!("{:.4}", expected_value(c)));
+        assert_eq!(
+            format!("{:.4}", second_moment - mean * mean),
+            format!("{:.4}", variance(c))
+        );
+    }
+    #[test]
+    fn continuous_density_increases_toward_cost() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let cost = 1.0;
+        let n = 100_000;
+        let bins = 10;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = vec![0usize; bins];
+        let mut fractional = 0;
+        for _ in 0..n {
+            let t = sample_continuous_with_rng(cost, &mut rng);
+            assert!((0.0..=cost).contains(&t));
+            if t.fract() != 0.0 {
+                fractional += 1;
+            }
+            counts[((t / cost * bins as f64) as usize).min(bins - 1)] += 1;
+        }
+        assert!(fractional > n / 2);
+        assert!(counts.windows(2).all(|w| w[0] < w[1]));
+    }
+    #[test]
+    fn cdf_is_normalized_and_monotonic() {
+        for c in [1, 3, 10, 1000] {
+            assert_eq!(0.0, cdf(0, c));
+            assert!((cdf(c, c) - 1.0).abs() < 1e-12);
+            assert!((0..c).all(|t| cdf(t, c) < cdf(t + 1, c)));
+        }
+        // The cdf is the integral of the pdf.
+        let (t, c) = (2, 3);
+        let steps = 100_000;
+        let dt = t as f64 / steps as f64;
+        let e = std::f64::consts::E;
+        let integral: f64 = (0..steps)
+            .map(|i| ((i as f64 + 0.5) * dt / c as f64).exp() / ((e - 1.0) * c as f64) * dt)
+            .sum();
+        assert!((integral - cdf(t, c)).abs() < 1e-6);
+    }
+    #[test]
+    fn normalized_pdf_sums_to_one() {
+        for c in [0, 1, 3, 10, 1000] {
+            let total: f64 = (0..=c).map(|t| normalized_pdf(t, c)).sum();
+            assert!((total - 1.0).abs() < 1e-12);
+            let norm: f64 = (0..=c).map(|t| pdf(t, c)).sum();
+            for t in (1..=c).step_by(1 + c as usize / 10) {
+                assert!((normalized_pdf(t, c) - pdf(t, c) / norm).abs() < 1e-12);
+            }
+        }
+        assert_eq!(0.0, normalized_pdf(4, 3));
+    }
+    #[test]
+    fn batch_matches_single_samples() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let cost = 10;
+        let n = 10_000;
+        let batch = sample_batch_with_rng(cost, n, &mut StdRng::seed_from_u64(9));
+        let mut rng = StdRng::seed_from_u64(9);
+        let single: Vec<u64> = (0..n).map(|_| sample_with_rng(cost, &mut rng)).collect();
+        assert_eq!(single, batch);
+
+        let mean = |samples: &[u64]| samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+        let other = sample_batch_with_rng(cost, n, &mut StdRng::seed_from_u64(10));
+        assert_eq!(n, other.len());
+        assert!((mean(&other) - mean(&single)).abs() < 0.2);
+    }
+    #[test]
+    fn sampler_matches_repeated_free_function_calls() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let n = 100_000;
+        for cost in [0, 1, 3, 100] {
+            let mut sampler = KarlinSampler::new(cost);
+            let mut rng = StdRng::seed_from_u64(cost);
+            let cached: Vec<u64> = (0..n).map(|_| sampler.sample(&mut rng)).collect();
+            let mut rng = StdRng::seed_from_u64(cost);
+            let uncached: Vec<u64> = (0..n).map(|_| sample_with_rng(cost, &mut rng)).collect();
+            assert_eq!(uncached, cached);
+        }
+    }
+    #[test]
+    fn distribution_caches_a_sampler_per_cost() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let distribution = KarlinDistribution::default();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut expected_rng = StdRng::seed_from_u64(5);
+        for cost in [3, 3, 100, 100, 3] {
+            assert_eq!(
+                sample_with_rng(cost, &mut expected_rng),
+                distribution.sample(cost, &mut rng)
+            );
+            assert_eq!(Some(cost), distribution.sampler.get().map(|s| s.cost()));
+        }
+    }
+    #[test]
+    fn uniform_extremes_map_to_support_ends() {
+        for cost in [1, 3, 100] {
+            assert_eq!(0, sample_from_uniform(0.0, cost));
+            assert_eq!(cost, sample_from_uniform(1.0 - f64::EPSILON, cost));
+        }
+        assert_eq!(0, sample_from_uniform(0.5, 0));
+        let mut rng = rand::rngs::mock::StepRng::new(u64::MAX / 2, 0);
+        assert_eq!(sample_from_uniform(0.5, 10), sample_with_rng(10, &mut rng));
+    }
+    #[test]
+    fn two_tier_waits_are_ordered() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut marginal = StdRng::seed_from_u64(4);
+        for _ in 0..10_000 {
+            let (compress_wait, discard_wait) = sample_two_tier_with_rng(2, 3, &mut rng);
+            assert!(compress_wait <= discard_wait);
+            // The discard wait has the same marginal as a single sample.
+            assert_eq!(sample_with_rng(3, &mut marginal), discard_wait);
+        }
+    }
+    #[test]
+    fn samples_pass_chi_square_test() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let cost = 20;
+        // 0.99 quantile of the chi-square distribution with 20 degrees of freedom.
+        let critical_value = 37.566;
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples: Vec<u64> = (0..100_000)
+            .map(|_| sample_with_rng(cost, &mut rng))
+            .collect();
+        assert!(chi_square_statistic(&samples, cost) < critical_value);
+        // A uniform sampler over the same support is caught.
+        let uniform: Vec<u64> = (0..100_000).map(|_| rng.gen_range(0..=cost)).collect();
+        assert!(chi_square_statistic(&uniform, cost) > critical_value);
+    }
 }
 
-/// Parametrized by c, creates a pdf with an expected value of (1 / ((e - 1) * C)).
+/// Parametrized by c, creates a pdf on [0, C] whose moments are given by
+/// [`expected_value`] and [`variance`]. As a continuous density it integrates to 1
+/// over [0, C], but its values at the integers do not sum to 1; the discrete
+/// sampler draws from [`normalized_pdf`] instead.
 pub fn pdf(t: u64, c: u64) -> f64 {
     let e = std::f64::consts::E;
     let lhs = 1.0 / ((e - 1.0) * c as f64);
@@ -19,18 +208,249 @@ pub fn pdf(t: u64, c: u64) -> f64 {
     lhs * rhs
 }
 
-/// Monte carlo sampling method for the karlin pdf.
+/// Probability that the discrete sampler returns the integer t in [0, C]. This is
+/// [`pdf`] rescaled so that it sums to 1 over the C + 1 integers of the support: with
+/// r = e^(1/C), it equals r^t (r - 1) / (r^(C + 1) - 1).
+pub fn normalized_pdf(t: u64, c: u64) -> f64 {
+    if t > c {
+        return 0.0;
+    }
+    if c == 0 {
+        return 1.0;
+    }
+    let c = c as f64;
+    (t as f64 / c).exp() * (1.0 / c).exp_m1() / ((c + 1.0) / c).exp_m1()
+}
+
+/// Probability that a sample of the continuous karlin density is at most t, i.e. the
+/// integral of [`pdf`] from 0 to t, which is normalized so that cdf(C, C) is 1.
+pub fn cdf(t: u64, c: u64) -> f64 {
+    if t >= c {
+        return 1.0;
+    }
+    let e = std::f64::consts::E;
+    (t as f64 / c as f64).exp_m1() / (e - 1.0)
+}
+
+/// Expected value of the continuous karlin density on [0, C], C / (e - 1).
+pub fn expected_value(c: u64) -> f64 {
+    let e = std::f64::consts::E;
+    c as f64 / (e - 1.0)
+}
+
+/// Variance of the continuous karlin density on [0, C], from its second moment
+/// C^2 (e - 2) / (e - 1).
+pub fn variance(c: u64) -> f64 {
+    let e = std::f64::consts::E;
+    let c = c as f64;
+    c * c * (e * e - 3.0 * e + 1.0) / ((e - 1.0) * (e - 1.0))
+}
+
+/// Samples the karlin pdf using the thread-local rng.
 pub fn sample(cost: u64) -> u64 {
-    let max_iters = 10_000;
-    let mut rng = thread_rng();
-    let max_value: f64 = pdf(cost, cost);
-    for _ in 0..max_iters {
-        let rand_x = rng.gen_range(0..=cost);
-        let rand_y = max_value * rng.gen_range(0.0f64..1.0f64);
-        let calc_y = pdf(rand_x, cost);
-        if rand_y <= calc_y {
-            return rand_x;
-        }
-    }
-    panic!("could not find through {} iterations", max_iters)
+    sample_with_rng(cost, &mut thread_rng())
+}
+
+/// Samples [`normalized_pdf`] over the integers in [0, C] from the provided rng, so
+/// that runs can be reproduced from a seed. Its cumulative distribution is the
+/// geometric sum (r^(t + 1) - 1) / (r^(C + 1) - 1), which we invert for a single
+/// uniform draw. As C grows this approaches the continuous density on [0, C], whose
+/// mean is C / (e - 1). Consuming exactly one draw per sample keeps seeded streams
+/// shared between randomized algorithms in step.
+pub fn sample_with_rng<R: Rng>(cost: u64, rng: &mut R) -> u64 {
+    sample_from_uniform(rng.gen(), cost)
+}
+
+/// Samples a coupled pair of compress and discard waits using the thread-local rng.
+pub fn sample_two_tier(compress_cost: u64, discard_cost: u64) -> (u64, u64) {
+    sample_two_tier_with_rng(compress_cost, discard_cost, &mut thread_rng())
+}
+
+/// Samples a pair of compress and discard waits from the provided rng, each with the
+/// karlin distribution for its own cost, coupled so that the compress wait never
+/// exceeds the discard wait.
+///
+/// The joint distribution is the comonotone coupling: both waits are quantiles of the
+/// same uniform draw. In the continuous limit this makes the compress wait exactly
+/// compress_cost / discard_cost times the discard wait, so the joint density lives on
+/// that line. Each marginal is unchanged from [`sample_with_rng`], and since the
+/// quantile of the discrete distribution grows with the cost, compress_cost <=
+/// discard_cost orders the pair by construction; the final clamp only guards other
+/// inputs.
+pub fn sample_two_tier_with_rng<R: Rng>(
+    compress_cost: u64,
+    discard_cost: u64,
+    rng: &mut R,
+) -> (u64, u64) {
+    let waits = sample_tiers_with_rng(&[compress_cost, discard_cost], rng);
+    (waits[0], waits[1])
+}
+
+/// Generalizes [`sample_two_tier_with_rng`] to any number of tiers: draws one wait per
+/// cost from the same uniform, and clamps each to the waits after it so that they never
+/// decrease.
+pub fn sample_tiers_with_rng<R: Rng>(costs: &[u64], rng: &mut R) -> Vec<u64> {
+    let u: f64 = rng.gen();
+    let mut waits: Vec<u64> = costs
+        .iter()
+        .map(|&cost| sample_from_uniform(u, cost))
+        .collect();
+    for i in (1..waits.len()).rev() {
+        waits[i - 1] = waits[i - 1].min(waits[i]);
+    }
+    waits
+}
+
+/// Draws `n` samples using the thread-local rng.
+pub fn sample_batch(cost: u64, n: usize) -> Vec<u64> {
+    sample_batch_with_rng(cost, n, &mut thread_rng())
+}
+
+/// Draws `n` samples from the provided rng, computing the normalizer of the
+/// cumulative distribution once for the whole batch. Yields the same samples as `n`
+/// calls to [`sample_with_rng`] on the same rng.
+pub fn sample_batch_with_rng<R: Rng>(cost: u64, n: usize, rng: &mut R) -> Vec<u64> {
+    let mut sampler = KarlinSampler::new(cost);
+    (0..n).map(|_| sampler.sample(rng)).collect()
+}
+
+/// A distribution over the keep cost a randomized two-tier algorithm waits to pay
+/// before discarding, sampled afresh after every access.
+pub trait WaitDistribution {
+    /// Samples a wait in [0, cost].
+    fn sample(&self, cost: u64, rng: &mut impl Rng) -> u64;
+    /// Probability that [`WaitDistribution::sample`] returns `wait`.
+    fn pdf(&self, wait: u64, cost: u64) -> f64;
+}
+
+/// The discretized Karlin density, which minimizes the worst-case expected ratio. It
+/// caches a [`KarlinSampler`] for the last cost it sampled, since an instance samples
+/// the same cost after every access.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KarlinDistribution {
+    sampler: Cell<Option<KarlinSampler>>,
+}
+
+impl WaitDistribution for KarlinDistribution {
+    fn sample(&self, cost: u64, rng: &mut impl Rng) -> u64 {
+        let mut sampler = match self.sampler.get() {
+            Some(sampler) if sampler.cost() == cost => sampler,
+            _ => {
+                let sampler = KarlinSampler::new(cost);
+                self.sampler.set(Some(sampler));
+                sampler
+            }
+        };
+        sampler.sample(rng)
+    }
+    fn pdf(&self, wait: u64, cost: u64) -> f64 {
+        normalized_pdf(wait, cost)
+    }
+}
+
+/// Waits drawn uniformly from the integers in [0, cost]. Short idle gaps often find
+/// the object already discarded, so its worst-case ratio approaches 2.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UniformDistribution;
+
+impl WaitDistribution for UniformDistribution {
+    fn sample(&self, cost: u64, rng: &mut impl Rng) -> u64 {
+        rng.gen_range(0..=cost)
+    }
+    fn pdf(&self, wait: u64, cost: u64) -> f64 {
+        if wait > cost {
+            0.0
+        } else {
+            1.0 / (cost + 1) as f64
+        }
+    }
+}
+
+/// Samples [`normalized_pdf`] for a fixed cost, computing the normalizer of the
+/// cumulative distribution once up front rather than on every draw. Yields the same
+/// samples as [`sample_with_rng`] on the same rng.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KarlinSampler {
+    cost: u64,
+    total: f64,
+}
+
+impl KarlinSampler {
+    pub fn new(cost: u64) -> Self {
+        let total = if cost == 0 {
+            0.0
+        } else {
+            cdf_total(cost as f64)
+        };
+        Self { cost, total }
+    }
+    pub fn cost(&self) -> u64 {
+        self.cost
+    }
+    pub fn sample(&mut self, rng: &mut impl Rng) -> u64 {
+        let u = rng.gen();
+        if self.cost == 0 {
+            return 0;
+        }
+        invert_cdf(u, self.cost as f64, self.total)
+    }
+}
+
+/// Maps a uniform draw u in [0, 1) through the inverse of the discrete cumulative
+/// distribution to a sample in [0, C], which is how every sampler in this module turns
+/// its draw into a threshold. Passing u directly makes sampling deterministic, e.g. to
+/// test how a given threshold drives an instance's policy. Draws near 0 give 0 and
+/// draws near 1 give C.
+pub fn sample_from_uniform(u: f64, cost: u64) -> u64 {
+    if cost == 0 {
+        return 0;
+    }
+    invert_cdf(u, cost as f64, cdf_total(cost as f64))
+}
+
+/// r^(C + 1) - 1 with r = e^(1/C), the unnormalized mass of the discrete support.
+fn cdf_total(c: f64) -> f64 {
+    ((c + 1.0) / c).exp() - 1.0
+}
+
+fn invert_cdf(u: f64, c: f64, total: f64) -> u64 {
+    let t = (c * (u * total).ln_1p()).ceil() - 1.0;
+    t.clamp(0.0, c) as u64
+}
+
+/// Samples the continuous karlin density over the real interval [0, C] using the
+/// thread-local rng.
+pub fn sample_continuous(cost: f64) -> f64 {
+    sample_continuous_with_rng(cost, &mut thread_rng())
+}
+
+/// Samples the continuous karlin density over [0, C] from the provided rng by
+/// inverting its cumulative distribution (e^(t/C) - 1) / (e - 1) for a single uniform
+/// draw, so that thresholds are not limited to integers for small costs.
+pub fn sample_continuous_with_rng<R: Rng>(cost: f64, rng: &mut R) -> f64 {
+    let u: f64 = rng.gen();
+    if cost <= 0.0 {
+        return 0.0;
+    }
+    let e = std::f64::consts::E;
+    (cost * (u * (e - 1.0)).ln_1p()).min(cost)
+}
+
+/// Pearson's chi-square statistic of samples against [`normalized_pdf`], with one bin
+/// per integer in [0, C], so C degrees of freedom.
+#[cfg(test)]
+pub(crate) fn chi_square_statistic(samples: &[u64], cost: u64) -> f64 {
+    let mut counts = vec![0usize; cost as usize + 1];
+    for &sample in samples {
+        counts[sample as usize] += 1;
+    }
+    let n = samples.len() as f64;
+    counts
+        .iter()
+        .enumerate()
+        .map(|(t, &observed)| {
+            let expected = n * normalized_pdf(t as u64, cost);
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum()
 }
@@ -0,0 +1,650 @@
+/// Logs a policy transition at debug level along with the condition that triggered
+/// it, when the `log` feature is enabled. Without the feature it expands to nothing,
+/// so the reason is never formatted.
+macro_rules! log_transition {
+    ($t:expr, $from:expr, $to:expr, $($reason:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "tick {}: {:?} -> {:?}, {}",
+            $t,
+            $from,
+            $to,
+            format_args!($($reason)+)
+        );
+    };
+}
+
+pub mod analysis;
+pub mod compress_tier;
+pub mod config;
+pub mod continuous;
+pub mod hint;
+pub mod karlin;
+pub mod multi_object;
+pub mod n_tier;
+pub mod optimal_offline;
+pub mod report;
+pub mod simulation;
+pub mod stream;
+pub mod three_tier;
+pub mod two_tier;
+pub mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Policy {
+    Keep,
+    Discard,
+    Compress,
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Policy::Keep => "keep",
+            Policy::Discard => "discard",
+            Policy::Compress => "compress",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An online or offline algorithm that is ticked once per unit of time.
+///
+/// Ticks are counted in `u64`, so a run can last until tick `u64::MAX`. Ticking past
+/// it panics rather than wrapping around, and costs that would overflow saturate.
+///
+/// Every instance in this crate is `Clone`, and a clone is a full snapshot of its
+/// state, including the thresholds a randomized instance has sampled and its rng. To
+/// restore a snapshot, replace the instance with the clone. A restored instance only
+/// repeats the same future thresholds if its rng clones its state, e.g. `StdRng`;
+/// clones of `ThreadRng` share the thread-local generator.
+pub trait Algorithm {
+    fn tick(&mut self, access: bool);
+    fn total_accrued_cost(&self) -> f64;
+    /// Cost charged by the most recent tick.
+    fn last_tick_cost(&self) -> f64;
+    /// The tier the object is currently stored in.
+    fn current_policy(&self) -> Policy;
+    /// Restores the instance to its initial state so it can be reused for another
+    /// run, resampling any random thresholds.
+    fn reset(&mut self);
+    /// Accrued cost split into what was paid to hold the object and what was paid to
+    /// recover it.
+    fn cost_breakdown(&self) -> CostBreakdown;
+    /// Number of accesses that had to recover the object.
+    fn recovery_count(&self) -> u64 {
+        self.cost_breakdown().num_recoveries
+    }
+    /// Number of times the object left the top tier.
+    fn discard_count(&self) -> u64 {
+        self.cost_breakdown().num_discards
+    }
+    /// Worst-case competitive ratio the algorithm guarantees against the offline
+    /// optimum, in expectation for randomized algorithms, so that an empirical ratio
+    /// above it flags a bug. Infinite for algorithms with no guarantee.
+    fn competitive_bound(&self) -> f64 {
+        f64::INFINITY
+    }
+}
+
+/// Boxed instances run like the instance they hold, so algorithms chosen at runtime,
+/// e.g. by [`two_tier::Strategy::build`], can be simulated.
+impl<T: Algorithm + ?Sized> Algorithm for Box<T> {
+    fn tick(&mut self, access: bool) {
+        (**self).tick(access);
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        (**self).total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        (**self).last_tick_cost()
+    }
+    fn current_policy(&self) -> Policy {
+        (**self).current_policy()
+    }
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        (**self).cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        (**self).competitive_bound()
+    }
+}
+
+/// A numeric type that costs can be given in. Instances convert costs to f64 when
+/// they are created, so integer and fractional costs share one implementation.
+pub trait Cost: Copy {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_cost {
+    ($($ty:ty),*) => {
+        $(
+            impl Cost for $ty {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+// i32 is included as the type unsuffixed integer literals default to, so instances
+// reject negative costs when they convert them.
+impl_cost!(i32, u32, u64, usize, f32, f64);
+
+/// Where an instance's accrued cost came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostBreakdown {
+    /// Cost of holding the object in its tier on idle ticks.
+    pub time_cost: f64,
+    /// Cost of recovering the object on accesses.
+    pub recovery_cost: f64,
+    /// Number of accesses that found the object outside the top tier.
+    pub num_recoveries: u64,
+    /// Number of times the object left the top tier, whether it was discarded or
+    /// moved to an intermediate tier.
+    pub num_discards: u64,
+}
+
+/// What happened to an instance during a single simulated tick.
+#[derive(Debug, Clone)]
+pub struct TickRecord {
+    pub t: u64,
+    pub accessed: bool,
+    /// Policy in effect at the end of the tick.
+    pub policy: Policy,
+    pub delta_cost: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulator<T: Algorithm> {
+    t: u64,
+    /// Tick of the access list the first simulated tick stands for, 1 unless the
+    /// simulator is zero-indexed.
+    first_tick: u64,
+    access: HashSet<u64>,
+    node: T,
+}
+
+impl<T: Algorithm> Simulator<T> {
+    /// Creates a simulator over the given access ticks, e.g. a `Vec` or an iterator
+    /// borrowing a slice. They are collected into a set once here, so each tick looks
+    /// up whether it is an access in O(1) rather than scanning the whole list.
+    ///
+    /// The first simulated tick is tick 1, so an access at tick 0 is never seen and
+    /// `run(n)` accrues costs over ticks 1 through n.
+    pub fn new(access: impl IntoIterator<Item = u64>, node: T) -> Self {
+        Self {
+            t: 0,
+            first_tick: 1,
+            access: access.into_iter().collect(),
+            node,
+        }
+    }
+    /// Like [`Simulator::new`], but for access lists that count from tick 0, e.g.
+    /// external traces. The first simulated tick accounts for tick 0, so an access
+    /// there is seen and `run(n)` accrues costs over ticks 0 through n - 1. Ticks
+    /// reported in traces count from 0 as well.
+    ///
+    /// The node still counts its own ticks from 1, so instances given the access list
+    /// themselves, such as offline ones, need it shifted one tick later.
+    ///
+    /// An access at tick `u64::MAX` is dropped: it would be the node's tick
+    /// `u64::MAX + 1`, past the longest horizon a simulation can run.
+    pub fn new_zero_indexed(access: impl IntoIterator<Item = u64>, node: T) -> Self {
+        Self {
+            t: 0,
+            first_tick: 0,
+            access: access
+                .into_iter()
+                .filter_map(|t| t.checked_add(1))
+                .collect(),
+            node,
+        }
+    }
+    pub fn tick(&mut self) {
+        self.t = crate::next_tick(self.t);
+        let should_access = self.access.contains(&self.t);
+        self.node.tick(should_access);
+    }
+    /// Advances the simulation by `num_ticks` ticks.
+    pub fn run(&mut self, num_ticks: u64) {
+        for _ in 0..num_ticks {
+            self.tick();
+        }
+    }
+    /// Advances the simulation by `num_ticks` ticks, recording each one.
+    pub fn run_with_trace(&mut self, num_ticks: u64) -> Vec<TickRecord> {
+        (0..num_ticks)
+            .map(|_| {
+                self.tick();
+                TickRecord {
+                    t: self.t - 1 + self.first_tick,
+                    accessed: self.access.contains(&self.t),
+                    policy: self.node.current_policy(),
+                    delta_cost: self.node.last_tick_cost(),
+                }
+            })
+            .collect()
+    }
+    /// Copies the simulation at its current tick, continuing with a different access
+    /// list for the ticks that follow, e.g. to explore what-if futures from a shared
+    /// past. Accesses at or before the current tick have no effect.
+    pub fn fork(&self, access: impl IntoIterator<Item = u64>) -> Self
+    where
+        T: Clone,
+    {
+        let shift = 1 - self.first_tick;
+        Self {
+            t: self.t,
+            first_tick: self.first_tick,
+            access: access
+                .into_iter()
+                .filter_map(|t| t.checked_add(shift))
+                .collect(),
+            node: self.node.clone(),
+        }
+    }
+    pub fn node(&self) -> &T {
+        &self.node
+    }
+    /// Ticks until the last access in the access list has been processed.
+    pub fn run_to_completion(&mut self) {
+        let last_access = self.access.iter().copied().max().unwrap_or(0);
+        while self.t < last_access {
+            self.tick();
+        }
+    }
+}
+
+/// A simulator that snapshots its node before every tick so it can be stepped
+/// backward. Every snapshot is a full clone of the node, so memory grows with
+/// the number of ticks taken.
+#[derive(Debug)]
+pub struct SteppableSimulator<T: Algorithm + Clone> {
+    sim: Simulator<T>,
+    history: Vec<(u64, T)>,
+}
+
+impl<T: Algorithm + Clone> SteppableSimulator<T> {
+    pub fn new(access: Vec<u64>, node: T) -> Self {
+        Self {
+            sim: Simulator::new(access, node),
+            history: vec![],
+        }
+    }
+    pub fn tick(&mut self) {
+        self.history.push((self.sim.t, self.sim.node.clone()));
+        self.sim.tick();
+    }
+    /// Undoes the most recent tick, returning false if there is nothing to undo.
+    pub fn step_back(&mut self) -> bool {
+        let Some((t, node)) = self.history.pop() else {
+            return false;
+        };
+        self.sim.t = t;
+        self.sim.node = node;
+        true
+    }
+    pub fn t(&self) -> u64 {
+        self.sim.t
+    }
+    pub fn node(&self) -> &T {
+        &self.sim.node
+    }
+}
+
+/// The tick after `t`.
+///
+/// # Panics
+///
+/// Panics if `t` is `u64::MAX`, the longest supported horizon.
+pub(crate) fn next_tick(t: u64) -> u64 {
+    t.checked_add(1)
+        .expect("simulations cannot run past tick u64::MAX")
+}
+
+/// Ratio of an online cost to the offline optimum's cost. When the offline instance
+/// pays nothing, e.g. over an empty access list, the ratio is 1.0 if the online
+/// instance paid nothing either and infinity otherwise, rather than NaN.
+pub fn competitive_ratio(online_cost: f64, offline_cost: f64) -> f64 {
+    if offline_cost > 0.0 {
+        online_cost / offline_cost
+    } else if online_cost > 0.0 {
+        f64::INFINITY
+    } else {
+        1.0
+    }
+}
+
+/// Additive regret of an online cost against the offline optimum's cost, rounded to
+/// the nearest integer. Unlike the ratio, it stays small when the offline instance
+/// pays little, and it is never negative for a correct offline optimum.
+pub fn regret(online_cost: f64, offline_cost: f64) -> i64 {
+    (online_cost - offline_cost).round() as i64
+}
+
+/// Runs an online instance over the access list for `num_ticks` ticks and returns its
+/// ratio to an offline cost computed beforehand, so that one offline run can be shared
+/// by every online instance compared on the same list.
+pub fn online_ratio<T: Algorithm>(
+    instance: T,
+    access_list: &[u64],
+    num_ticks: u64,
+    offline_cost: f64,
+) -> f64 {
+    let mut sim = Simulator::new(access_list.iter().copied(), instance);
+    sim.run(num_ticks);
+    competitive_ratio(sim.node.total_accrued_cost(), offline_cost)
+}
+
+/// Asserts that a competitive ratio lies within `[lower, upper]`.
+///
+/// # Panics
+///
+/// Panics, naming the ratio and the bounds, if it lies outside them or is NaN.
+#[track_caller]
+pub fn assert_ratio_bounds(ratio: f64, lower: f64, upper: f64) {
+    assert!(
+        (lower..=upper).contains(&ratio),
+        "competitive ratio {} outside [{}, {}]",
+        ratio,
+        lower,
+        upper
+    );
+}
+
+/// Worst-case competitive ratios the two-tier algorithms guarantee in continuous
+/// time. The randomized bound is on the expected ratio, so a single randomized run
+/// may exceed it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompetitiveBounds {
+    /// Best ratio a deterministic online algorithm can guarantee, 2.
+    pub deterministic: f64,
+    /// Best expected ratio a randomized online algorithm can guarantee, e / (e - 1).
+    pub randomized: f64,
+}
+
+impl CompetitiveBounds {
+    pub fn theoretical() -> Self {
+        Self {
+            deterministic: two_tier::deterministic_lower_bound(),
+            randomized: two_tier::theoretical_competitive_ratio(),
+        }
+    }
+    /// Whether a deterministic ratio is no better than the offline optimum and no
+    /// worse than the deterministic bound.
+    pub fn within_deterministic(&self, ratio: f64) -> bool {
+        (1.0..=self.deterministic).contains(&ratio)
+    }
+    /// Whether an expected randomized ratio is no better than the offline optimum and
+    /// no worse than the randomized bound.
+    pub fn within_randomized(&self, ratio: f64) -> bool {
+        (1.0..=self.randomized).contains(&ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::two_tier::NaiveInstance;
+    use rand::SeedableRng;
+    #[test]
+    fn step_back_restores_snapshot() {
+        let mut sim = SteppableSimulator::new(vec![2, 3], NaiveInstance::new(1, 3));
+        sim.tick();
+        let after_first_tick = format!("{:?}", sim.node());
+        sim.tick();
+        sim.tick();
+        assert_eq!(3, sim.t());
+        assert!(sim.step_back());
+        assert!(sim.step_back());
+        assert_eq!(1, sim.t());
+        assert_eq!(after_first_tick, format!("{:?}", sim.node()));
+        assert!(sim.step_back());
+        assert!(!sim.step_back());
+        assert_eq!(0, sim.t());
+    }
+    #[test]
+    fn run_matches_manual_ticks() {
+        let access_list = vec![3, 9, 10, 17];
+        let mut manual = Simulator::new(access_list.clone(), NaiveInstance::new(1, 3));
+        for _ in 0..17 {
+            manual.tick();
+        }
+        let mut run = Simulator::new(access_list.clone(), NaiveInstance::new(1, 3));
+        run.run(17);
+        let mut completed = Simulator::new(access_list, NaiveInstance::new(1, 3));
+        completed.run_to_completion();
+        assert_eq!(17, completed.t);
+        assert_eq!(
+            manual.node.total_accrued_cost(),
+            run.node.total_accrued_cost()
+        );
+        assert_eq!(
+            manual.node.total_accrued_cost(),
+            completed.node.total_accrued_cost()
+        );
+    }
+    #[test]
+    fn set_lookup_matches_linear_scan() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let access_list = crate::util::generate_access_list_with_rng(5_000, 50_000, &mut rng);
+        let num_ticks = 50_000;
+        let mut sim = Simulator::new(access_list.clone(), NaiveInstance::new(1, 3));
+        sim.run(num_ticks);
+        let mut node = NaiveInstance::new(1, 3);
+        for t in 1..=num_ticks {
+            node.tick(access_list.contains(&t));
+        }
+        assert_eq!(node.total_accrued_cost(), sim.node.total_accrued_cost());
+    }
+    #[test]
+    fn trace_costs_sum_to_total() {
+        let access_list = vec![4, 8, 12, 30];
+        let mut sim = Simulator::new(access_list.clone(), NaiveInstance::new(1, 3));
+        let trace = sim.run_with_trace(30);
+        assert_eq!(30, trace.len());
+        let total: f64 = trace.iter().map(|record| record.delta_cost).sum();
+        assert_eq!(sim.node.total_accrued_cost(), total);
+        let accessed: Vec<u64> = trace
+            .iter()
+            .filter(|record| record.accessed)
+            .map(|record| record.t)
+            .collect();
+        assert_eq!(access_list, accessed);
+        // Recovering on the last access charges the recovery cost.
+        assert_eq!(3.0, trace[29].delta_cost);
+    }
+    #[test]
+    fn cost_breakdown_sums_to_total() {
+        // Each access follows 2 kept ticks and a discard, so recovers for 3.
+        let mut sim = Simulator::new(vec![4, 8, 12], NaiveInstance::new(1, 3));
+        sim.run(11);
+        let breakdown = sim.node.cost_breakdown();
+        assert_eq!(
+            CostBreakdown {
+                time_cost: 6.0,
+                recovery_cost: 6.0,
+                num_recoveries: 2,
+                num_discards: 3,
+            },
+            breakdown
+        );
+        assert_eq!(
+            sim.node.total_accrued_cost(),
+            breakdown.time_cost + breakdown.recovery_cost
+        );
+    }
+    #[test]
+    fn policy_equality_and_display() {
+        let mut sim = Simulator::new(vec![4], NaiveInstance::new(1, 3));
+        assert_eq!(Policy::Keep, sim.node.current_policy());
+        sim.run(3);
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+        assert_ne!(Policy::Keep, Policy::Compress);
+        let names: Vec<String> = [Policy::Keep, Policy::Discard, Policy::Compress]
+            .iter()
+            .map(Policy::to_string)
+            .collect();
+        assert_eq!(["keep", "discard", "compress"], names[..]);
+    }
+    #[test]
+    fn regret_is_the_difference_from_the_optimum() {
+        use crate::two_tier::{offline_cost, KarlinInstance};
+        use rand::Rng;
+        // The online pays 2 + 3 over the first gap and 1 over the second, while the
+        // offline pays 3 + 1.
+        let access_list = [4, 6];
+        let mut sim = Simulator::new(access_list, NaiveInstance::new(1, 3));
+        sim.run(6);
+        assert_eq!(
+            2,
+            regret(
+                sim.node.total_accrued_cost(),
+                offline_cost(1, 3, &access_list, 6)
+            )
+        );
+        // A tiny optimum makes the ratio explode while the regret stays small.
+        assert_eq!(f64::INFINITY, competitive_ratio(2.0, 0.0));
+        assert_eq!(2, regret(2.0, 0.0));
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        for _ in 0..100 {
+            let access_list = util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let offline = offline_cost(1, 3, &access_list, num_ticks);
+            let mut naive = Simulator::new(access_list.iter().copied(), NaiveInstance::new(1, 3));
+            naive.run(num_ticks);
+            assert!(regret(naive.node.total_accrued_cost(), offline) >= 0);
+            let karlin =
+                KarlinInstance::with_rng(1, 3, rand::rngs::StdRng::seed_from_u64(rng.gen()));
+            let mut karlin = Simulator::new(access_list.iter().copied(), karlin);
+            karlin.run(num_ticks);
+            assert!(regret(karlin.node.total_accrued_cost(), offline) >= 0);
+        }
+    }
+    #[test]
+    fn zero_indexed_simulator_accounts_for_tick_zero() {
+        use crate::two_tier::AlwaysKeepInstance;
+        let access_list = vec![2, 5];
+        // Ticks 1, 3 and 4 are idle.
+        let mut sim = Simulator::new(access_list.clone(), AlwaysKeepInstance::new(1));
+        sim.run(5);
+        assert_eq!(3.0, sim.node.total_accrued_cost());
+        // Ticks 0, 1, 3 and 4 are idle.
+        let mut sim = Simulator::new_zero_indexed(access_list.clone(), AlwaysKeepInstance::new(1));
+        let trace = sim.run_with_trace(6);
+        assert_eq!(4.0, sim.node.total_accrued_cost());
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 5],
+            trace.iter().map(|record| record.t).collect::<Vec<_>>()
+        );
+        assert!(trace[2].accessed && trace[5].accessed);
+
+        // An access at tick 0 is only seen when zero-indexed.
+        let mut sim = Simulator::new(vec![0, 3], AlwaysKeepInstance::new(1));
+        assert!(!sim.run_with_trace(3)[0].accessed);
+        let mut sim = Simulator::new_zero_indexed(vec![0, 3], AlwaysKeepInstance::new(1));
+        assert!(sim.run_with_trace(4)[0].accessed);
+        assert_eq!(2.0, sim.node.total_accrued_cost());
+
+        // The last zero-indexed tick cannot be reached, so its access is dropped.
+        let sim = Simulator::new_zero_indexed(vec![0, u64::MAX], AlwaysKeepInstance::new(1));
+        assert_eq!(HashSet::from([1]), sim.access);
+    }
+    #[test]
+    fn forked_snapshot_replays_trajectory() {
+        use crate::two_tier::KarlinInstance;
+        use rand::rngs::StdRng;
+        let access_list = vec![3, 9, 14, 20];
+        let online = KarlinInstance::with_rng(1, 5, StdRng::seed_from_u64(7));
+        let mut sim = Simulator::new(access_list.clone(), online);
+        sim.run(10);
+        let snapshot = sim.fork(access_list);
+        let mut restored = snapshot.clone();
+        let deltas = |trace: Vec<TickRecord>| -> Vec<f64> {
+            trace.iter().map(|record| record.delta_cost).collect()
+        };
+        assert_eq!(
+            deltas(sim.run_with_trace(20)),
+            deltas(restored.run_with_trace(20))
+        );
+        assert_eq!(
+            sim.node().sampled_discard_thresholds(),
+            restored.node().sampled_discard_thresholds()
+        );
+
+        // A different future from the same snapshot keeps the shared past.
+        let mut alternative = snapshot.fork(vec![11, 12]);
+        alternative.run(20);
+        assert_eq!(30, alternative.t);
+        assert_eq!(
+            snapshot.node().sampled_discard_thresholds(),
+            &alternative.node().sampled_discard_thresholds()[..3]
+        );
+    }
+    #[test]
+    fn zero_offline_cost_ratio() {
+        assert_eq!(1.0, competitive_ratio(0.0, 0.0));
+        assert_eq!(f64::INFINITY, competitive_ratio(3.0, 0.0));
+        assert_eq!(1.5, competitive_ratio(3.0, 2.0));
+    }
+    #[test]
+    fn ratio_within_bounds_passes() {
+        assert_ratio_bounds(1.5, 1.0, 2.0);
+        assert_ratio_bounds(2.0, 1.0, 2.0);
+        let bounds = CompetitiveBounds::theoretical();
+        assert!(bounds.within_deterministic(1.8));
+        assert!(!bounds.within_deterministic(2.1));
+        assert!(bounds.within_randomized(1.5));
+        assert!(!bounds.within_randomized(1.6));
+        assert!(!bounds.within_randomized(0.9));
+        assert!(!bounds.within_randomized(f64::NAN));
+    }
+    #[test]
+    #[should_panic(expected = "competitive ratio 2.5 outside [1, 2]")]
+    fn ratio_above_bounds_panics() {
+        assert_ratio_bounds(2.5, 1.0, 2.0);
+    }
+    #[test]
+    #[should_panic(expected = "outside")]
+    fn nan_ratio_panics() {
+        assert_ratio_bounds(f64::NAN, 1.0, 2.0);
+    }
+    #[cfg(feature = "log")]
+    #[test]
+    fn transitions_are_logged() {
+        use std::sync::Mutex;
+        struct Capture(Mutex<Vec<String>>);
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+            fn flush(&self) {}
+        }
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mut sim = Simulator::new(vec![4], crate::two_tier::BreakEvenInstance::new(1, 3));
+        sim.run(4);
+        let logs = CAPTURE.0.lock().unwrap();
+        assert!(logs.contains(
+            &"tick 3: Keep -> Discard, discard: keep cost paid 3 >= threshold 3".to_string()
+        ));
+        assert!(logs.contains(&"tick 4: Discard -> Keep, recover: accessed".to_string()));
+    }
+}
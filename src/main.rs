@@ -1,67 +1,243 @@
-mod karlin;
-mod three_tier;
-mod two_tier;
-mod util;
+use clap::{Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::error::Error;
+use std::io;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+use storage_optimization_problem::analysis::{
+    competitive_ratio_stats, competitive_ratio_stats_seeded, RatioStats,
+};
+use storage_optimization_problem::config::ExperimentConfig;
+use storage_optimization_problem::report::{self, RunReport};
+use storage_optimization_problem::three_tier::{self, Costs};
+use storage_optimization_problem::util::AccessGeneration;
+use storage_optimization_problem::{n_tier, stream, two_tier};
 
-use two_tier::{calculate_competitive_ratio, KarlinInstance, NaiveInstance};
-
-#[derive(Debug, Clone)]
-pub enum Policy {
-    Keep,
-    Discard,
-    Compress,
+/// The storage hierarchy to run the experiment on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Tiers {
+    /// Keep or discard.
+    Two,
+    /// Keep, compress or discard, where compressing halves both the keep cost and the
+    /// recover cost.
+    Three,
 }
 
-pub trait Algorithm {
-    fn tick(&mut self, access: bool);
-    fn total_accrued_cost(&self) -> f64;
+/// How results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One line of ratios per run, followed by summary statistics.
+    Text,
+    /// One CSV row of costs per run of the randomized algorithm.
+    Csv,
 }
 
-#[derive(Debug)]
-pub struct Simulator<T: Algorithm> {
-    t: u64,
-    access: Vec<u64>,
-    node: T,
+/// Compares the deterministic and randomized strategies against the offline optimum
+/// across a variety of random access lists.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Cost of keeping the object for a tick.
+    #[arg(long, default_value_t = 1)]
+    keep_cost: u64,
+    /// Cost of recovering the object after it was discarded.
+    #[arg(long, default_value_t = 3)]
+    recover_cost: u64,
+    /// Number of random access lists to run.
+    #[arg(long, default_value_t = 100)]
+    runs: usize,
+    /// Number of fresh access lists of 10 accesses within 100 ticks that the two-tier
+    /// summary statistics are computed over.
+    #[arg(long, default_value_t = 1000)]
+    summary_runs: usize,
+    /// Number of distinct accesses in each list.
+    #[arg(long, default_value_t = 10)]
+    list_len: usize,
+    /// Latest tick an access can be drawn at.
+    #[arg(long, default_value_t = 100)]
+    max_tick: u64,
+    /// Seed for the access lists and sampled thresholds, for reproducible runs.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Storage hierarchy to run on.
+    #[arg(long, value_enum, default_value_t = Tiers::Two)]
+    tiers: Tiers,
+    /// How results are printed.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Read access ticks from stdin, one per line, and print the running costs of the
+    /// randomized two-tier algorithm after each access instead of running experiments.
+    #[arg(long)]
+    stream: bool,
+    /// Read the costs, number of runs, seed and how access lists are generated from a
+    /// TOML experiment config instead of their flags.
+    #[cfg(feature = "config")]
+    #[arg(long)]
+    config: Option<PathBuf>,
 }
 
-impl<T: Algorithm> Simulator<T> {
-    pub fn new(access: Vec<u64>, node: T) -> Self {
-        Self { t: 0, access, node }
-    }
-    pub fn tick(&mut self) {
-        self.t += 1;
-        let should_access = self.access.contains(&self.t);
-        self.node.tick(should_access);
+impl Args {
+    /// The experiment given by `--config`, or else by the flags.
+    fn experiment(&self) -> Result<ExperimentConfig, Box<dyn Error>> {
+        #[cfg(feature = "config")]
+        if let Some(path) = &self.config {
+            return Ok(storage_optimization_problem::config::load(path)?);
+        }
+        Ok(ExperimentConfig {
+            keep_cost: self.keep_cost,
+            recover_cost: self.recover_cost,
+            runs: self.runs,
+            seed: self.seed,
+            access: AccessGeneration::Uniform {
+                list_len: self.list_len,
+                max_tick: self.max_tick,
+            },
+        })
     }
 }
 
-/// We show the randomized strategy for the two-tier problem across
-/// a variety of random access lists.
-fn main() {
-    let keep_cost = 1u64;
-    let recover_cost = 3u64;
-    for _ in 0..100 {
-        let access_list = util::generate_access_list(10, 100);
-        let num_ticks = access_list.last().unwrap().clone();
-        let online = NaiveInstance::new(keep_cost, recover_cost);
-        let deterministic_competitive_ratio = calculate_competitive_ratio(
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let experiment = args.experiment()?;
+    let ExperimentConfig {
+        keep_cost,
+        recover_cost,
+        runs,
+        ..
+    } = experiment;
+    let mut rng = match experiment.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    if args.stream {
+        if args.tiers != Tiers::Two {
+            return Err("--stream only supports two tiers".into());
+        }
+        let online = two_tier::KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        stream::run_stream(
+            io::stdin().lock(),
+            io::stdout().lock(),
             online,
             keep_cost,
             recover_cost,
-            access_list.clone(),
-            num_ticks,
+        )?;
+        return Ok(());
+    }
+    let costs = Costs {
+        keep_time_cost: keep_cost as f64,
+        compressed_time_cost: keep_cost as f64 / 2.0,
+        recover_from_compressed_cost: recover_cost as f64 / 2.0,
+        recover_from_discard_cost: recover_cost as f64,
+    };
+    if args.tiers == Tiers::Three {
+        costs.validate()?;
+    }
+    let mut deterministic_reports = Vec::with_capacity(runs);
+    let mut randomized_reports = Vec::with_capacity(runs);
+    for run_index in 0..runs {
+        let access_list = experiment.access.generate(&mut rng)?;
+        // An empty list, e.g. from `--list-len 0`, runs for no ticks.
+        let num_ticks = access_list.last().copied().unwrap_or(0);
+        let offline_list = || access_list.clone().into_iter().peekable();
+        let (deterministic, randomized) = match args.tiers {
+            Tiers::Two => (
+                RunReport::new(
+                    run_index,
+                    two_tier::NaiveInstance::new(keep_cost, recover_cost),
+                    two_tier::OfflineInstance::new(keep_cost, recover_cost, offline_list()),
+                    &access_list,
+                    num_ticks,
+                ),
+                RunReport::new(
+                    run_index,
+                    two_tier::KarlinInstance::with_rng(keep_cost, recover_cost, &mut rng),
+                    two_tier::OfflineInstance::new(keep_cost, recover_cost, offline_list()),
+                    &access_list,
+                    num_ticks,
+                ),
+            ),
+            Tiers::Three => (
+                RunReport::new(
+                    run_index,
+                    n_tier::NaiveInstance::new(costs.tiers())?,
+                    three_tier::OfflineInstance::new(costs.clone(), offline_list())?,
+                    &access_list,
+                    num_ticks,
+                ),
+                RunReport::new(
+                    run_index,
+                    three_tier::KarlinInstance::with_rng(costs.clone(), &mut rng)?,
+                    three_tier::OfflineInstance::new(costs.clone(), offline_list())?,
+                    &access_list,
+                    num_ticks,
+                ),
+            ),
+        };
+        deterministic_reports.push(deterministic);
+        randomized_reports.push(randomized);
+    }
+    if args.format == Format::Csv {
+        report::write_csv(io::stdout().lock(), &randomized_reports)?;
+        return Ok(());
+    }
+    for (deterministic, randomized) in deterministic_reports.iter().zip(&randomized_reports) {
+        println!(
+            "ratio: deterministic={:.2}, randomized={:.2}",
+            deterministic.competitive_ratio, randomized.competitive_ratio,
         );
-        let online = KarlinInstance::new(keep_cost, recover_cost);
-        let randomized_competitive_ratio = calculate_competitive_ratio(
-            online,
-            keep_cost,
-            recover_cost,
-            access_list.clone(),
-            num_ticks,
+    }
+    if args.tiers == Tiers::Two && args.summary_runs > 0 {
+        let (deterministic, randomized) = match experiment.seed {
+            Some(seed) => (
+                competitive_ratio_stats_seeded(
+                    |_| two_tier::NaiveInstance::new(keep_cost, recover_cost),
+                    keep_cost,
+                    recover_cost,
+                    args.summary_runs,
+                    seed,
+                ),
+                competitive_ratio_stats_seeded(
+                    |rng| two_tier::KarlinInstance::with_rng(keep_cost, recover_cost, rng),
+                    keep_cost,
+                    recover_cost,
+                    args.summary_runs,
+                    seed,
+                ),
+            ),
+            None => (
+                competitive_ratio_stats(
+                    || two_tier::NaiveInstance::new(keep_cost, recover_cost),
+                    keep_cost,
+                    recover_cost,
+                    args.summary_runs,
+                ),
+                competitive_ratio_stats(
+                    || two_tier::KarlinInstance::new(keep_cost, recover_cost),
+                    keep_cost,
+                    recover_cost,
+                    args.summary_runs,
+                ),
+            ),
+        };
+        println!(
+            "deterministic over {} runs: {}",
+            args.summary_runs, deterministic
         );
+        println!("randomized over {} runs: {}", args.summary_runs, randomized);
+    } else if args.tiers == Tiers::Three && runs > 0 {
+        let ratios = |reports: &[RunReport]| {
+            RatioStats::from_ratios(reports.iter().map(|r| r.competitive_ratio).collect())
+        };
         println!(
-            "ratio: deterministic={:.2}, randomized={:.2}",
-            deterministic_competitive_ratio, randomized_competitive_ratio,
+            "deterministic over {} runs: {}",
+            runs,
+            ratios(&deterministic_reports)
+        );
+        println!(
+            "randomized over {} runs: {}",
+            runs,
+            ratios(&randomized_reports)
         );
     }
+    Ok(())
 }
@@ -0,0 +1,373 @@
+//! Capacity-constrained storage of many objects, where at most `capacity` objects
+//! can be kept at once and any other object must be recovered on access. With a
+//! capacity of zero every access recovers its object, which is evicted right away.
+use crate::{competitive_ratio, karlin, Algorithm, Simulator};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+
+pub trait CacheAlgorithm {
+    fn tick(&mut self, access: usize);
+    fn total_accrued_cost(&self) -> f64;
+}
+
+#[derive(Debug, Clone)]
+pub struct LruInstance {
+    capacity: usize,
+    keep_cost: u64,
+    recover_costs: Vec<u64>,
+    accrued_cost: f64,
+    // Kept objects ordered from least to most recently accessed.
+    kept: Vec<usize>,
+}
+
+impl LruInstance {
+    pub fn new(capacity: usize, keep_cost: u64, recover_costs: Vec<u64>) -> Self {
+        Self {
+            capacity,
+            keep_cost,
+            recover_costs,
+            accrued_cost: 0.0,
+            kept: Vec::with_capacity(capacity + 1),
+        }
+    }
+}
+
+impl CacheAlgorithm for LruInstance {
+    fn tick(&mut self, access: usize) {
+        match self.kept.iter().position(|&obj| obj == access) {
+            Some(idx) => {
+                self.kept.remove(idx);
+            }
+            // Incur a recovery cost for an object we previously discarded.
+            None => self.accrued_cost += self.recover_costs[access] as f64,
+        }
+        self.kept.push(access);
+        // Discard the least recently accessed object when over capacity.
+        if self.kept.len() > self.capacity {
+            self.kept.remove(0);
+        }
+        self.accrued_cost += (self.keep_cost * self.kept.len() as u64) as f64;
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+}
+
+/// Landlord with unit object sizes: each kept object holds credit equal to its
+/// recovery cost, and evictions discard whichever object runs out of credit first.
+#[derive(Debug, Clone)]
+pub struct LandlordInstance {
+    capacity: usize,
+    keep_cost: u64,
+    recover_costs: Vec<u64>,
+    accrued_cost: f64,
+    // Kept objects along with their remaining credit.
+    kept: Vec<(usize, f64)>,
+}
+
+impl LandlordInstance {
+    pub fn new(capacity: usize, keep_cost: u64, recover_costs: Vec<u64>) -> Self {
+        Self {
+            capacity,
+            keep_cost,
+            recover_costs,
+            accrued_cost: 0.0,
+            kept: Vec::with_capacity(capacity + 1),
+        }
+    }
+}
+
+impl CacheAlgorithm for LandlordInstance {
+    fn tick(&mut self, access: usize) {
+        let credit = self.recover_costs[access] as f64;
+        match self.kept.iter_mut().find(|(obj, _)| *obj == access) {
+            Some(entry) => entry.1 = credit,
+            None => {
+                // Make room by charging rent to every kept object until one is broke.
+                if self.kept.len() == self.capacity && self.capacity > 0 {
+                    let rent = self
+                        .kept
+                        .iter()
+                        .map(|&(_, c)| c)
+                        .fold(f64::INFINITY, f64::min);
+                    self.kept.iter_mut().for_each(|entry| entry.1 -= rent);
+                    if let Some(idx) = self.kept.iter().position(|&(_, c)| c <= 0.0) {
+                        self.kept.remove(idx);
+                    }
+                }
+                self.accrued_cost += credit;
+                if self.capacity > 0 {
+                    self.kept.push((access, credit));
+                }
+            }
+        }
+        self.accrued_cost += (self.keep_cost * self.kept.len() as u64) as f64;
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+}
+
+/// Runs the randomized two-tier algorithm for each object: after every access the
+/// object samples a Karlin threshold for its recover cost and is discarded once the
+/// keep cost paid since that access reaches it. When an access leaves more objects
+/// kept than fit, the one closest to its threshold is evicted early.
+#[derive(Debug, Clone)]
+pub struct KarlinPerObjectInstance<R: Rng = ThreadRng> {
+    capacity: usize,
+    keep_cost: u64,
+    recover_costs: Vec<u64>,
+    accrued_cost: f64,
+    // Kept objects along with the keep cost paid since their last access and the
+    // threshold sampled at that access.
+    kept: Vec<(usize, u64, u64)>,
+    rng: R,
+}
+
+impl KarlinPerObjectInstance {
+    pub fn new(capacity: usize, keep_cost: u64, recover_costs: Vec<u64>) -> Self {
+        Self::with_rng(capacity, keep_cost, recover_costs, thread_rng())
+    }
+}
+
+impl<R: Rng> KarlinPerObjectInstance<R> {
+    /// Creates an instance that samples its thresholds from the given rng, which makes
+    /// runs reproducible when the rng is seeded.
+    pub fn with_rng(capacity: usize, keep_cost: u64, recover_costs: Vec<u64>, rng: R) -> Self {
+        Self {
+            capacity,
+            keep_cost,
+            recover_costs,
+            accrued_cost: 0.0,
+            kept: Vec::with_capacity(capacity + 1),
+            rng,
+        }
+    }
+}
+
+impl<R: Rng> CacheAlgorithm for KarlinPerObjectInstance<R> {
+    fn tick(&mut self, access: usize) {
+        let recover_cost = self.recover_costs[access];
+        match self.kept.iter().position(|&(obj, _, _)| obj == access) {
+            Some(idx) => {
+                self.kept.remove(idx);
+            }
+            None => self.accrued_cost += recover_cost as f64,
+        }
+        // Every other kept object pays to be held through this tick, and is discarded
+        // first if that would reach its threshold.
+        let keep_cost = self.keep_cost;
+        self.kept.retain_mut(|(_, paid, threshold)| {
+            *paid = paid.saturating_add(keep_cost);
+            *paid < *threshold
+        });
+        let threshold = karlin::sample_with_rng(recover_cost, &mut self.rng);
+        self.kept.push((access, 0, threshold));
+        if self.kept.len() > self.capacity {
+            let idx = (0..self.kept.len())
+                .min_by_key(|&idx| {
+                    let (_, paid, threshold) = self.kept[idx];
+                    threshold.saturating_sub(paid)
+                })
+                .unwrap();
+            self.kept.remove(idx);
+        }
+        self.accrued_cost += (self.keep_cost * self.kept.len() as u64) as f64;
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+}
+
+/// Runs a cache algorithm over a workload where each entry is the object
+/// accessed at that tick, returning the total accrued cost.
+pub fn run_workload<T: CacheAlgorithm>(mut instance: T, workload: &[usize]) -> f64 {
+    for &access in workload {
+        instance.tick(access);
+    }
+    instance.total_accrued_cost()
+}
+
+/// Runs an online and an offline instance for each of several objects, every object
+/// with its own access list, and aggregates their costs.
+#[derive(Debug)]
+pub struct MultiSimulator<A: Algorithm, B: Algorithm> {
+    objects: Vec<(Simulator<A>, Simulator<B>)>,
+}
+
+impl<A: Algorithm, B: Algorithm> MultiSimulator<A, B> {
+    /// Creates a simulator over `(access_list, online, offline)` triples, one per object.
+    pub fn new(objects: Vec<(Vec<u64>, A, B)>) -> Self {
+        Self {
+            objects: objects
+                .into_iter()
+                .map(|(access, online, offline)| {
+                    (
+                        Simulator::new(access.clone(), online),
+                        Simulator::new(access, offline),
+                    )
+                })
+                .collect(),
+        }
+    }
+    pub fn tick(&mut self) {
+        for (online, offline) in &mut self.objects {
+            online.tick();
+            offline.tick();
+        }
+    }
+    /// Advances every object by `num_ticks` ticks.
+    pub fn run(&mut self, num_ticks: u64) {
+        for _ in 0..num_ticks {
+            self.tick();
+        }
+    }
+    pub fn total_online_cost(&self) -> f64 {
+        self.objects
+            .iter()
+            .map(|(online, _)| online.node.total_accrued_cost())
+            .sum()
+    }
+    pub fn total_offline_cost(&self) -> f64 {
+        self.objects
+            .iter()
+            .map(|(_, offline)| offline.node.total_accrued_cost())
+            .sum()
+    }
+    /// Competitive ratio of each object, in the order the objects were given.
+    pub fn ratios(&self) -> Vec<f64> {
+        self.objects
+            .iter()
+            .map(|(online, offline)| {
+                competitive_ratio(
+                    online.node.total_accrued_cost(),
+                    offline.node.total_accrued_cost(),
+                )
+            })
+            .collect()
+    }
+    /// Unweighted mean of the per-object competitive ratios.
+    pub fn mean_ratio(&self) -> f64 {
+        self.ratios().iter().sum::<f64>() / self.objects.len() as f64
+    }
+    /// Competitive ratio of the whole workload, i.e. the per-object ratios weighted by
+    /// each object's offline cost.
+    pub fn aggregate_ratio(&self) -> f64 {
+        competitive_ratio(self.total_online_cost(), self.total_offline_cost())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn lru_underperforms_cost_aware() {
+        // One expensive object interleaved with two cheap ones that thrash the
+        // remaining slot. LRU keeps evicting the expensive object.
+        let recover_costs = vec![100, 1, 1];
+        let workload: Vec<usize> = [0, 1, 2, 1, 2].repeat(10);
+        let lru = run_workload(LruInstance::new(2, 1, recover_costs.clone()), &workload);
+        let landlord = run_workload(LandlordInstance::new(2, 1, recover_costs), &workload);
+        assert!(lru > landlord);
+        // LRU recovers the expensive object on each of the ten cycles while Landlord
+        // recovers it once, at the price of twenty extra cheap recoveries.
+        assert_eq!(9.0 * 100.0 - 20.0, lru - landlord);
+    }
+    #[test]
+    fn karlin_per_object_beats_lru() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let recover_costs = vec![100, 1, 1];
+        let workload: Vec<usize> = [0, 1, 2, 1, 2].repeat(10);
+        let lru = run_workload(LruInstance::new(2, 1, recover_costs.clone()), &workload);
+        let landlord = run_workload(
+            LandlordInstance::new(2, 1, recover_costs.clone()),
+            &workload,
+        );
+        let karlin = run_workload(
+            KarlinPerObjectInstance::with_rng(2, 1, recover_costs, StdRng::seed_from_u64(3)),
+            &workload,
+        );
+        // Like Landlord, it holds on to the expensive object and thrashes the cheap
+        // ones, whose thresholds never exceed their recover cost of 1.
+        assert!(karlin < lru);
+        assert!((karlin - landlord).abs() < 0.1 * landlord);
+    }
+    #[test]
+    fn zero_capacity_recovers_every_access() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let recover_costs = vec![5, 3];
+        let workload = [0, 1, 0, 0, 1];
+        let expected = 5.0 + 3.0 + 5.0 + 5.0 + 3.0;
+        assert_eq!(
+            expected,
+            run_workload(LruInstance::new(0, 1, recover_costs.clone()), &workload)
+        );
+        assert_eq!(
+            expected,
+            run_workload(
+                LandlordInstance::new(0, 1, recover_costs.clone()),
+                &workload
+            )
+        );
+        assert_eq!(
+            expected,
+            run_workload(
+                KarlinPerObjectInstance::with_rng(0, 1, recover_costs, StdRng::seed_from_u64(3)),
+                &workload
+            )
+        );
+    }
+    #[test]
+    fn multi_simulator_aggregates_objects() {
+        let access_lists = [vec![4, 8, 12], vec![2, 3], vec![5, 30]];
+        let mut sim = MultiSimulator::new(
+            access_lists
+                .iter()
+                .map(|access_list| {
+                    let offline = crate::two_tier::OfflineInstance::new(
+                        1,
+                        3,
+                        access_list.clone().into_iter().peekable(),
+                    );
+                    (
+                        access_list.clone(),
+                        crate::two_tier::NaiveInstance::new(1, 3),
+                        offline,
+                    )
+                })
+                .collect(),
+        );
+        sim.run(30);
+        let mut offline_costs = vec![];
+        let mut ratios = vec![];
+        for access_list in &access_lists {
+            let offline = crate::two_tier::OfflineInstance::new(
+                1,
+                3,
+                access_list.clone().into_iter().peekable(),
+            );
+            let mut offline_sim = Simulator::new(access_list.clone(), offline);
+            offline_sim.run(30);
+            offline_costs.push(offline_sim.node.total_accrued_cost());
+            ratios.push(crate::two_tier::calculate_competitive_ratio(
+                crate::two_tier::NaiveInstance::new(1, 3),
+                1,
+                3,
+                access_list.clone(),
+                30,
+            ));
+        }
+        assert_eq!(ratios, sim.ratios());
+        assert_eq!(ratios.iter().sum::<f64>() / 3.0, sim.mean_ratio());
+        let weighted: f64 = ratios
+            .iter()
+            .zip(&offline_costs)
+            .map(|(ratio, cost)| ratio * cost)
+            .sum();
+        let total_offline: f64 = offline_costs.iter().sum();
+        assert_eq!(total_offline, sim.total_offline_cost());
+        assert!((weighted / total_offline - sim.aggregate_ratio()).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,682 @@
+//! Storage hierarchies with any number of tiers, ordered from the most expensive to
+//! hold and cheapest to recover from down to the cheapest to hold. The two-tier and
+//! three-tier problems are the special cases of keep/discard and
+//! keep/compress/discard.
+use crate::util::{validate_access_list, AccessListError};
+use crate::{karlin, Algorithm, CostBreakdown, Policy};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::fmt;
+use std::iter::Peekable;
+
+/// A tier the object can be stored in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tier {
+    /// Cost of holding the object in this tier for a tick.
+    pub time_cost: f64,
+    /// Cost of bringing the object back to the top tier when it is accessed.
+    pub recover_cost: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TierError {
+    /// The hierarchy has no tier to keep the object in.
+    NoTiers,
+    /// Tier `index` costs more to hold or less to recover from than the tier above it.
+    Unordered { index: usize },
+    /// The object cannot start in tier `index` of a hierarchy of `num_tiers` tiers.
+    NoSuchTier { index: usize, num_tiers: usize },
+    /// The access list is not sorted and distinct, or accesses tick 0.
+    AccessList(AccessListError),
+}
+
+impl fmt::Display for TierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TierError::NoTiers => write!(f, "a storage hierarchy needs at least one tier"),
+            TierError::Unordered { index } => write!(
+                f,
+                "tier {} must be no more expensive to hold and no cheaper to recover from than tier {}",
+                index,
+                index - 1
+            ),
+            TierError::NoSuchTier { index, num_tiers } => write!(
+                f,
+                "tier {} is outside a hierarchy of {} tiers",
+                index, num_tiers
+            ),
+            TierError::AccessList(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TierError {}
+
+impl From<AccessListError> for TierError {
+    fn from(err: AccessListError) -> Self {
+        TierError::AccessList(err)
+    }
+}
+
+/// Checks that there is a top tier and that each tier below it trades a lower holding
+/// cost for a higher recovery cost.
+pub fn validate_tiers(tiers: &[Tier]) -> Result<(), TierError> {
+    if tiers.is_empty() {
+        return Err(TierError::NoTiers);
+    }
+    for (index, pair) in tiers.windows(2).enumerate() {
+        if pair[1].time_cost > pair[0].time_cost || pair[1].recover_cost < pair[0].recover_cost {
+            return Err(TierError::Unordered { index: index + 1 });
+        }
+    }
+    Ok(())
+}
+
+/// Policy reported for a tier: the top tier keeps the object as is, the bottom tier
+/// discards it, and any tier in between compresses it.
+pub fn tier_policy(tier: usize, num_tiers: usize) -> Policy {
+    if tier == 0 {
+        Policy::Keep
+    } else if tier + 1 == num_tiers {
+        Policy::Discard
+    } else {
+        Policy::Compress
+    }
+}
+
+/// Shared state of the online algorithms, which demote the object to the deepest tier
+/// whose threshold the keep cost paid since the last access has reached, and recover
+/// it to the top tier on access.
+#[derive(Debug, Clone)]
+struct OnlineState {
+    t: u64,
+    tiers: Vec<Tier>,
+    tier: usize,
+    initial_tier: usize,
+    last_access: u64,
+    thresholds: Vec<f64>,
+    /// Whether the keep cost paid since the last access counts the current tick, as
+    /// the deterministic thresholds do. Sampled thresholds only count the ticks before
+    /// it, so that a threshold of 0 demotes on the first idle tick and a threshold of
+    /// one tick's keep cost on the second.
+    includes_current_tick: bool,
+    /// Last tick on which the object may not be demoted; see
+    /// [`NaiveInstance::hold_for`].
+    hold_until: u64,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+}
+
+impl OnlineState {
+    fn new(tiers: Vec<Tier>, thresholds: Vec<f64>, includes_current_tick: bool) -> Self {
+        Self {
+            t: 0,
+            tiers,
+            tier: 0,
+            initial_tier: 0,
+            last_access: 0,
+            thresholds,
+            includes_current_tick,
+            hold_until: 0,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+        }
+    }
+    /// Advances one tick, returning whether the object was accessed.
+    fn tick(&mut self, access: bool) -> bool {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        if !access {
+            self.demote();
+            self.accrued_cost += self.tiers[self.tier].time_cost;
+            self.breakdown.time_cost += self.tiers[self.tier].time_cost;
+            return false;
+        }
+        self.last_access = self.t;
+        // Incur a recovery cost if necessary.
+        self.accrued_cost += self.tiers[self.tier].recover_cost;
+        if self.tier != 0 {
+            self.breakdown.recovery_cost += self.tiers[self.tier].recover_cost;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(
+                self.t,
+                tier_policy(self.tier, self.tiers.len()),
+                Policy::Keep,
+                "recover from tier {}: accessed",
+                self.tier
+            );
+        }
+        self.tier = 0;
+        true
+    }
+    /// Moves the object down to the deepest tier whose threshold the keep cost paid
+    /// since the last access has reached. Only idle ticks demote, so an access always
+    /// finds the object where the previous tick left it.
+    fn demote(&mut self) {
+        if self.t <= self.hold_until {
+            return;
+        }
+        let mut idle_ticks = self.t.saturating_sub(self.last_access);
+        if !self.includes_current_tick {
+            idle_ticks = idle_ticks.saturating_sub(1);
+        }
+        let keep_cost_paid = idle_ticks as f64 * self.tiers[0].time_cost;
+        let reached = self
+            .thresholds
+            .iter()
+            .rposition(|&threshold| keep_cost_paid >= threshold)
+            .unwrap_or(0);
+        if reached > self.tier {
+            log_transition!(
+                self.t,
+                tier_policy(self.tier, self.tiers.len()),
+                tier_policy(reached, self.tiers.len()),
+                "demote to tier {}: keep cost paid {} >= threshold {}",
+                reached,
+                keep_cost_paid,
+                self.thresholds[reached]
+            );
+            if self.tier == 0 {
+                self.breakdown.num_discards += 1;
+            }
+            self.tier = reached;
+        }
+    }
+    /// Starts the object in the given tier, validated against the hierarchy, so that
+    /// the first access recovers it from there.
+    fn start_in(&mut self, tier: usize) -> Result<(), TierError> {
+        if tier >= self.tiers.len() {
+            return Err(TierError::NoSuchTier {
+                index: tier,
+                num_tiers: self.tiers.len(),
+            });
+        }
+        self.tier = tier;
+        self.initial_tier = tier;
+        Ok(())
+    }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.tier = self.initial_tier;
+        self.last_access = 0;
+        self.hold_until = 0;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+}
+
+/// Deterministic algorithm that demotes the object to a tier once the keep cost paid
+/// since the last access reaches that tier's recovery cost.
+#[derive(Debug, Clone)]
+pub struct NaiveInstance {
+    state: OnlineState,
+}
+
+impl NaiveInstance {
+    pub fn new(tiers: Vec<Tier>) -> Result<Self, TierError> {
+        validate_tiers(&tiers)?;
+        let thresholds = tiers.iter().map(|tier| tier.recover_cost).collect();
+        Ok(Self {
+            state: OnlineState::new(tiers, thresholds, true),
+        })
+    }
+    /// Like [`NaiveInstance::new`], but starting with the object in the given tier,
+    /// e.g. the last tier for an object that begins in cold storage.
+    pub fn with_initial_tier(tiers: Vec<Tier>, tier: usize) -> Result<Self, TierError> {
+        let mut instance = Self::new(tiers)?;
+        instance.state.start_in(tier)?;
+        Ok(instance)
+    }
+    /// Index of the tier the object is currently stored in.
+    pub fn current_tier(&self) -> usize {
+        self.state.tier
+    }
+    /// Keeps the object where it is for the next `ticks` ticks, however much keep
+    /// cost they pay. Demotions that come due meanwhile happen on the first idle tick
+    /// after the hold.
+    pub(crate) fn hold_for(&mut self, ticks: u64) {
+        self.state.hold_until = self.state.t.saturating_add(ticks);
+    }
+}
+
+impl Algorithm for NaiveInstance {
+    fn tick(&mut self, access: bool) {
+        self.state.tick(access);
+    }
+    fn current_policy(&self) -> Policy {
+        tier_policy(self.state.tier, self.state.tiers.len())
+    }
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.state.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.state.accrued_cost - self.state.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.state.breakdown
+    }
+}
+
+/// Draws the thresholds of a [`KarlinInstance`], the keep cost paid since the last
+/// access at which the object moves to each tier, after every access.
+pub trait ThresholdSampler {
+    /// Samples one non-decreasing threshold per tier.
+    fn sample_thresholds<R: Rng>(&mut self, tiers: &[Tier], rng: &mut R) -> Vec<f64>;
+}
+
+/// Karlin thresholds for every tier from the same uniform draw, so that deeper tiers
+/// are never reached first; see [`karlin::sample_tiers_with_rng`]. Costs are truncated
+/// to integers for sampling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoupledKarlin;
+
+impl ThresholdSampler for CoupledKarlin {
+    fn sample_thresholds<R: Rng>(&mut self, tiers: &[Tier], rng: &mut R) -> Vec<f64> {
+        let costs: Vec<u64> = tiers.iter().map(|tier| tier.recover_cost as u64).collect();
+        karlin::sample_tiers_with_rng(&costs, rng)
+            .into_iter()
+            .map(|threshold| threshold as f64)
+            .collect()
+    }
+}
+
+/// Randomized algorithm that samples one threshold per tier after every access, by
+/// default Karlin thresholds coupled across the tiers. It demotes the object on the
+/// first idle tick whose keep cost paid before it reaches a tier's threshold.
+#[derive(Debug, Clone)]
+pub struct KarlinInstance<R: Rng = ThreadRng, S: ThresholdSampler = CoupledKarlin> {
+    state: OnlineState,
+    sampler: S,
+    rng: R,
+}
+
+impl KarlinInstance {
+    pub fn new(tiers: Vec<Tier>) -> Result<Self, TierError> {
+        Self::with_rng(tiers, thread_rng())
+    }
+}
+
+impl<R: Rng> KarlinInstance<R> {
+    /// Creates an instance that samples its thresholds from the provided rng, so that
+    /// runs can be reproduced from a seed.
+    pub fn with_rng(tiers: Vec<Tier>, rng: R) -> Result<Self, TierError> {
+        Self::with_sampler(tiers, CoupledKarlin, rng)
+    }
+}
+
+impl<R: Rng, S: ThresholdSampler> KarlinInstance<R, S> {
+    /// Creates an instance that draws its thresholds with `sampler` instead of the
+    /// coupled Karlin density.
+    pub fn with_sampler(tiers: Vec<Tier>, sampler: S, rng: R) -> Result<Self, TierError> {
+        validate_tiers(&tiers)?;
+        let mut instance = Self {
+            state: OnlineState::new(tiers, vec![], false),
+            sampler,
+            rng,
+        };
+        instance.resample_thresholds();
+        Ok(instance)
+    }
+    fn resample_thresholds(&mut self) {
+        self.state.thresholds = self
+            .sampler
+            .sample_thresholds(&self.state.tiers, &mut self.rng);
+    }
+    /// The sampler that draws the thresholds after every access.
+    pub fn sampler(&self) -> &S {
+        &self.sampler
+    }
+    pub(crate) fn sampler_mut(&mut self) -> &mut S {
+        &mut self.sampler
+    }
+    /// Starts the clock at tick `t`, as if the object had just been accessed then.
+    pub(crate) fn start_at(&mut self, t: u64) {
+        self.state.t = t;
+        self.state.last_access = t;
+    }
+    /// Charges an access that recovered the object `cost` more than its tier's
+    /// recovery cost, e.g. under a recovery cost that grows with idle time.
+    pub(crate) fn charge_extra_recovery(&mut self, cost: f64) {
+        self.state.accrued_cost += cost;
+        self.state.breakdown.recovery_cost += cost;
+    }
+    /// Index of the tier the object is currently stored in.
+    pub fn current_tier(&self) -> usize {
+        self.state.tier
+    }
+    /// Keep cost paid since the last access at which the object moves to each tier,
+    /// as sampled after that access.
+    pub fn thresholds(&self) -> &[f64] {
+        &self.state.thresholds
+    }
+}
+
+impl<R: Rng, S: ThresholdSampler> Algorithm for KarlinInstance<R, S> {
+    fn tick(&mut self, access: bool) {
+        if self.state.tick(access) {
+            self.resample_thresholds();
+        }
+    }
+    fn current_policy(&self) -> Policy {
+        tier_policy(self.state.tier, self.state.tiers.len())
+    }
+    fn reset(&mut self) {
+        self.state.reset();
+        self.resample_thresholds();
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.state.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.state.accrued_cost - self.state.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.state.breakdown
+    }
+}
+
+/// Omniscient algorithm. At the start of each idle gap it moves the object straight to
+/// the tier that is cheapest to hold it in until the next access and recover it from,
+/// preferring deeper tiers on ties. Once the access list is exhausted the object is
+/// never recovered again, so it moves to the bottom tier, which is the cheapest to
+/// hold, for the remaining ticks of the run.
+#[derive(Debug, Clone)]
+pub struct OfflineInstance<T>
+where
+    T: Iterator<Item = u64>,
+{
+    t: u64,
+    tiers: Vec<Tier>,
+    tier: usize,
+    initial_tier: usize,
+    access_list: Peekable<T>,
+    initial_access_list: Peekable<T>,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+}
+
+impl<T> OfflineInstance<T>
+where
+    T: Iterator<Item = u64> + Clone,
+{
+    pub fn new(tiers: Vec<Tier>, access_list: Peekable<T>) -> Result<Self, TierError> {
+        validate_tiers(&tiers)?;
+        Ok(Self {
+            t: 0,
+            tiers,
+            tier: 0,
+            initial_tier: 0,
+            initial_access_list: access_list.clone(),
+            access_list,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+        })
+    }
+    /// Like [`OfflineInstance::new`], but starting with the object in the given tier.
+    /// It stays there until the first access recovers it.
+    pub fn with_initial_tier(
+        tiers: Vec<Tier>,
+        access_list: Peekable<T>,
+        tier: usize,
+    ) -> Result<Self, TierError> {
+        let mut instance = Self::new(tiers, access_list)?;
+        if tier >= instance.tiers.len() {
+            return Err(TierError::NoSuchTier {
+                index: tier,
+                num_tiers: instance.tiers.len(),
+            });
+        }
+        instance.tier = tier;
+        instance.initial_tier = tier;
+        Ok(instance)
+    }
+    /// Index of the tier the object is currently stored in.
+    pub fn current_tier(&self) -> usize {
+        self.tier
+    }
+}
+
+impl<T> Algorithm for OfflineInstance<T>
+where
+    T: Iterator<Item = u64> + Clone,
+{
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        // Accesses behind the clock, e.g. duplicates or ticks the simulation never
+        // reaches, can no longer happen, so skip them rather than letting them stand in
+        // for the next access.
+        while self.access_list.next_if(|&elem| elem < self.t).is_some() {}
+        let bottom = self.tiers.len() - 1;
+        if self.access_list.peek().is_none() && !access && self.tier != bottom {
+            log_transition!(
+                self.t,
+                tier_policy(self.tier, self.tiers.len()),
+                tier_policy(bottom, self.tiers.len()),
+                "demote to tier {}: no access left",
+                bottom
+            );
+            if self.tier == 0 {
+                self.breakdown.num_discards += 1;
+            }
+            self.tier = bottom;
+        }
+        if let (0, Some(&elem)) = (self.tier, self.access_list.peek()) {
+            let idle = (elem - self.t) as f64;
+            let gap_cost = |tier: &Tier| tier.time_cost * idle + tier.recover_cost;
+            for (index, tier) in self.tiers.iter().enumerate() {
+                if gap_cost(tier) <= gap_cost(&self.tiers[self.tier]) {
+                    self.tier = index;
+                }
+            }
+            if self.tier != 0 {
+                log_transition!(
+                    self.t,
+                    Policy::Keep,
+                    tier_policy(self.tier, self.tiers.len()),
+                    "demote to tier {}: cheapest over {} idle ticks until the next access",
+                    self.tier,
+                    idle
+                );
+                self.breakdown.num_discards += 1;
+            }
+        }
+        if !access {
+            self.accrued_cost += self.tiers[self.tier].time_cost;
+            self.breakdown.time_cost += self.tiers[self.tier].time_cost;
+            return;
+        }
+        let _ = self.access_list.next();
+        // Incur a recovery cost if necessary.
+        self.accrued_cost += self.tiers[self.tier].recover_cost;
+        if self.tier != 0 {
+            self.breakdown.recovery_cost += self.tiers[self.tier].recover_cost;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(
+                self.t,
+                tier_policy(self.tier, self.tiers.len()),
+                Policy::Keep,
+                "recover from tier {}: accessed",
+                self.tier
+            );
+        }
+        self.tier = 0;
+    }
+    fn current_policy(&self) -> Policy {
+        tier_policy(self.tier, self.tiers.len())
+    }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.tier = self.initial_tier;
+        self.access_list = self.initial_access_list.clone();
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
+    fn competitive_bound(&self) -> f64 {
+        1.0
+    }
+}
+
+pub fn calculate_competitive_ratio<T: Algorithm, L: AsRef<[u64]>>(
+    instance: T,
+    tiers: Vec<Tier>,
+    access_list: L,
+    num_ticks: u64,
+) -> Result<f64, TierError> {
+    let access_list = access_list.as_ref();
+    let offline_cost = offline_cost(tiers, access_list, num_ticks)?;
+    Ok(crate::online_ratio(
+        instance,
+        access_list,
+        num_ticks,
+        offline_cost,
+    ))
+}
+
+/// Cost of the offline optimum over the access list for `num_ticks` ticks, which can
+/// be reused with [`crate::online_ratio`] across online instances. Accesses after
+/// `num_ticks` are ignored; see [`crate::util::trim_access_list`]. Fails if the access
+/// list is not sorted and distinct, or accesses tick 0; see [`validate_access_list`].
+pub fn offline_cost(
+    tiers: Vec<Tier>,
+    access_list: &[u64],
+    num_ticks: u64,
+) -> Result<f64, TierError> {
+    validate_access_list(access_list, u64::MAX)?;
+    let access_list = crate::util::trim_access_list(access_list, num_ticks);
+    let offline = OfflineInstance::new(tiers, access_list.iter().copied().peekable())?;
+    let mut sim = crate::Simulator::new(access_list.iter().copied(), offline);
+    sim.run(num_ticks);
+    Ok(sim.node.total_accrued_cost())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::optimal_offline::OptimalOffline;
+    fn two_tiers(keep_cost: f64, recover_cost: f64) -> Vec<Tier> {
+        vec![
+            Tier {
+                time_cost: keep_cost,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost,
+            },
+        ]
+    }
+    #[test]
+    fn two_competitive() {
+        let online = NaiveInstance::new(two_tiers(1.0, 3.0)).unwrap();
+        let ratio =
+            calculate_competitive_ratio(online, two_tiers(1.0, 3.0), vec![4, 8], 8).unwrap();
+        assert_eq!(5.0 / 3.0, ratio);
+        assert!(ratio <= 2.0);
+    }
+    #[test]
+    fn offline_matches_exact_optimum() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let tiers = vec![
+            Tier {
+                time_cost: 4.0,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 2.0,
+                recover_cost: 3.0,
+            },
+            Tier {
+                time_cost: 1.0,
+                recover_cost: 8.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: 20.0,
+            },
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let offline =
+                OfflineInstance::new(tiers.clone(), access_list.clone().into_iter().peekable())
+                    .unwrap();
+            let mut sim = crate::Simulator::new(access_list.clone(), offline);
+            sim.run(num_ticks);
+            let optimal = OptimalOffline::new(&tiers, &access_list, num_ticks);
+            assert_eq!(optimal.minimum_cost(), sim.node.total_accrued_cost());
+
+            let online =
+                KarlinInstance::with_rng(tiers.clone(), StdRng::seed_from_u64(rng.gen())).unwrap();
+            let ratio =
+                calculate_competitive_ratio(online, tiers.clone(), access_list, num_ticks).unwrap();
+            assert!(ratio >= 1.0);
+        }
+    }
+    #[test]
+    fn invalid_tiers_are_rejected() {
+        assert_eq!(Some(TierError::NoTiers), NaiveInstance::new(vec![]).err());
+        let mut tiers = two_tiers(1.0, 3.0);
+        tiers.push(Tier {
+            time_cost: 0.5,
+            recover_cost: 5.0,
+        });
+        assert_eq!(
+            Some(TierError::Unordered { index: 2 }),
+            KarlinInstance::new(tiers).err()
+        );
+        assert_eq!(
+            Some(TierError::NoSuchTier {
+                index: 2,
+                num_tiers: 2
+            }),
+            NaiveInstance::with_initial_tier(two_tiers(1.0, 3.0), 2).err()
+        );
+    }
+    #[test]
+    fn rejects_invalid_access_lists() {
+        let tiers = vec![
+            Tier {
+                time_cost: 1.0,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: 10.0,
+            },
+        ];
+        let online = KarlinInstance::new(tiers.clone()).unwrap();
+        assert_eq!(
+            Err(TierError::AccessList(AccessListError::Unsorted {
+                index: 1
+            })),
+            calculate_competitive_ratio(online, tiers.clone(), vec![5, 3], 10)
+        );
+        assert_eq!(
+            Err(TierError::AccessList(AccessListError::Duplicate {
+                tick: 3
+            })),
+            offline_cost(tiers, &[3, 3], 10)
+        );
+    }
+}
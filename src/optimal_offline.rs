@@ -0,0 +1,335 @@
+//! Exact offline optimum, found by dynamic programming over ticks and storage tiers.
+use crate::n_tier::{tier_policy, Tier};
+use crate::three_tier::Costs;
+use crate::Policy;
+use std::collections::HashSet;
+
+/// Minimum-cost policy sequence for a known access list, charging costs the way the
+/// simulated instances do: each idle tick pays the time cost of the tier the object is
+/// in, and each access pays the recovery cost of that tier before returning to Keep.
+/// Between accesses the object may only move down the tiers, since moving back up
+/// early costs a recovery without saving anything.
+#[derive(Debug, Clone)]
+pub struct OptimalOffline {
+    policies: Vec<Policy>,
+    minimum_cost: f64,
+}
+
+impl OptimalOffline {
+    /// Solves the two-tier problem, where the object is either kept or discarded.
+    pub fn two_tier(
+        keep_cost: u64,
+        recover_cost: u64,
+        access_list: &[u64],
+        num_ticks: u64,
+    ) -> Self {
+        let tiers = [
+            Tier {
+                time_cost: keep_cost as f64,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: recover_cost as f64,
+            },
+        ];
+        Self::new(&tiers, access_list, num_ticks)
+    }
+    /// Solves the three-tier problem, where the object can also be compressed.
+    pub fn three_tier(costs: &Costs, access_list: &[u64], num_ticks: u64) -> Self {
+        Self::new(&costs.tiers(), access_list, num_ticks)
+    }
+    /// Solves an N-tier hierarchy, ordered from the top tier downward. `cost[s]` is the
+    /// cheapest way to end the current tick in tier s, and `parents[t][s]` the tier the
+    /// previous tick ended in on that cheapest path.
+    pub fn new(tiers: &[Tier], access_list: &[u64], num_ticks: u64) -> Self {
+        let accesses: HashSet<u64> = access_list.iter().copied().collect();
+        let mut cost = vec![f64::INFINITY; tiers.len()];
+        cost[0] = 0.0;
+        let mut parents = Vec::with_capacity(num_ticks as usize);
+        for t in 1..=num_ticks {
+            let mut next_cost = vec![f64::INFINITY; tiers.len()];
+            let mut parent = vec![0; tiers.len()];
+            if accesses.contains(&t) {
+                // Recover from whichever tier is cheapest to have reached, ending in Keep.
+                for (from, from_cost) in cost.iter().enumerate() {
+                    for tier in &tiers[from..] {
+                        if from_cost + tier.recover_cost < next_cost[0] {
+                            next_cost[0] = from_cost + tier.recover_cost;
+                            parent[0] = from;
+                        }
+                    }
+                }
+            } else {
+                for (from, from_cost) in cost.iter().enumerate() {
+                    for (to, tier) in tiers.iter().enumerate().skip(from) {
+                        if from_cost + tier.time_cost < next_cost[to] {
+                            next_cost[to] = from_cost + tier.time_cost;
+                            parent[to] = from;
+                        }
+                    }
+                }
+            }
+            cost = next_cost;
+            parents.push(parent);
+        }
+        let mut tier =
+            (0..tiers.len()).fold(0, |best, s| if cost[s] < cost[best] { s } else { best });
+        let minimum_cost = cost[tier];
+        let mut policies = Vec::with_capacity(parents.len());
+        for parent in parents.iter().rev() {
+            policies.push(tier_policy(tier, tiers.len()));
+            tier = parent[tier];
+        }
+        policies.reverse();
+        Self {
+            policies,
+            minimum_cost,
+        }
+    }
+    /// Total cost of the optimal policy sequence.
+    pub fn minimum_cost(&self) -> f64 {
+        self.minimum_cost
+    }
+    /// Policy in effect at the end of each tick, starting with tick 1.
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies
+    }
+}
+
+/// Lower bound on the offline optimum from the linear-programming relaxation that lets
+/// each idle gap split fractionally across the tiers, paying each tier's time cost over
+/// the gap and its recovery cost at the access ending it in proportion. The relaxed
+/// optimum puts each gap entirely in its cheapest tier, so it is found in one pass over
+/// the accesses rather than over every tick. Idle ticks after the last access pay the
+/// cheapest time cost, with nothing to recover.
+///
+/// Since holding costs fall with depth, no gap gains from moving down part way
+/// through, so the relaxation is tight and the bound equals [`OptimalOffline`]'s
+/// minimum cost for a sorted, distinct access list.
+pub fn offline_lower_bound(tiers: &[Tier], access_list: &[u64], num_ticks: u64) -> f64 {
+    let mut last_access = 0;
+    let mut cost = 0.0;
+    for &t in access_list.iter().filter(|&&t| t <= num_ticks) {
+        let idle = t.saturating_sub(last_access + 1) as f64;
+        cost += tiers
+            .iter()
+            .map(|tier| tier.time_cost * idle + tier.recover_cost)
+            .fold(f64::INFINITY, f64::min);
+        last_access = t;
+    }
+    let trailing = num_ticks.saturating_sub(last_access) as f64;
+    cost + tiers
+        .iter()
+        .map(|tier| tier.time_cost * trailing)
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Cost of following a fixed policy sequence, given as the policy in effect at the end
+/// of each tick starting with tick 1 like [`OptimalOffline::policies`], charged the way
+/// [`OptimalOffline`] charges its own sequence: each idle tick pays the time cost of
+/// the tier its policy stores the object in, and each access pays the recovery cost of
+/// the tier the object was left in and returns it to the top tier, whatever the policy
+/// for that tick. Moving back up without an access pays the recovery cost of the tier
+/// left. `Compress` stores the object in the second tier.
+///
+/// # Panics
+///
+/// Panics if there is not one policy per tick, or if a policy is `Compress` and there
+/// are fewer than three tiers.
+pub fn cost_of_policy_sequence(
+    policies: &[Policy],
+    access_list: &[u64],
+    tiers: &[Tier],
+    num_ticks: u64,
+) -> f64 {
+    assert_eq!(
+        num_ticks,
+        policies.len() as u64,
+        "a policy sequence needs one policy per tick"
+    );
+    let accesses: HashSet<u64> = access_list.iter().copied().collect();
+    let mut tier = 0;
+    let mut cost = 0.0;
+    for (t, &policy) in (1..=num_ticks).zip(policies) {
+        if accesses.contains(&t) {
+            cost += tiers[tier].recover_cost;
+            tier = 0;
+            continue;
+        }
+        let next = policy_tier(policy, tiers.len());
+        if next < tier {
+            cost += tiers[tier].recover_cost;
+        }
+        tier = next;
+        cost += tiers[tier].time_cost;
+    }
+    cost
+}
+
+/// Tier of an N-tier hierarchy that holds an object under the given policy.
+fn policy_tier(policy: Policy, num_tiers: usize) -> usize {
+    match policy {
+        Policy::Keep => 0,
+        Policy::Compress => {
+            assert!(num_tiers > 2, "{} tiers have no compressed tier", num_tiers);
+            1
+        }
+        Policy::Discard => num_tiers - 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{three_tier, two_tier, Algorithm, Simulator};
+    #[test]
+    fn hand_verified_three_tier_optimum() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 6.0,
+        };
+        // Each gap has 5 idle ticks, for which keeping costs 5, discarding 6, and
+        // compressing right away 0.5 * 5 + 2 = 4.5.
+        let access_list = vec![6, 12];
+        let optimal = OptimalOffline::three_tier(&costs, &access_list, 12);
+        assert_eq!(9.0, optimal.minimum_cost());
+        let policies: Vec<String> = optimal
+            .policies()
+            .iter()
+            .map(|policy| format!("{:?}", policy))
+            .collect();
+        assert_eq!(
+            ["Compress", "Compress", "Compress", "Compress", "Compress", "Keep"],
+            policies[..6]
+        );
+
+        let greedy = three_tier::OfflineInstance::new(
+            costs.clone(),
+            access_list.clone().into_iter().peekable(),
+        )
+        .unwrap();
+        let mut sim = Simulator::new(access_list, greedy);
+        sim.run(12);
+        assert_eq!(optimal.minimum_cost(), sim.node.total_accrued_cost());
+    }
+    #[test]
+    fn greedy_offline_is_no_better() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+
+            let greedy = two_tier::OfflineInstance::new(
+                keep_cost,
+                recover_cost,
+                access_list.clone().into_iter().peekable(),
+            );
+            let mut sim = Simulator::new(access_list.clone(), greedy);
+            sim.run(num_ticks);
+            let optimal =
+                OptimalOffline::two_tier(keep_cost, recover_cost, &access_list, num_ticks);
+            assert_eq!(sim.node.total_accrued_cost(), optimal.minimum_cost());
+
+            let greedy = three_tier::OfflineInstance::new(
+                costs.clone(),
+                access_list.clone().into_iter().peekable(),
+            )
+            .unwrap();
+            let mut sim = Simulator::new(access_list.clone(), greedy);
+            sim.run(num_ticks);
+            let optimal = OptimalOffline::three_tier(&costs, &access_list, num_ticks);
+            assert_eq!(optimal.minimum_cost(), sim.node.total_accrued_cost());
+        }
+    }
+    #[test]
+    fn lower_bound_never_exceeds_optimum() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 6.0,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 60, &mut rng);
+            // Run past the last access, where the optimum discards for free.
+            let num_ticks = access_list.last().unwrap() + 5;
+            let optimal = OptimalOffline::three_tier(&costs, &access_list, num_ticks);
+            let bound = offline_lower_bound(&costs.tiers(), &access_list, num_ticks);
+            assert!(bound <= optimal.minimum_cost() + 1e-9);
+            assert!((optimal.minimum_cost() - bound).abs() < 1e-9);
+        }
+        let two_tiers = [
+            Tier {
+                time_cost: 1.0,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: 3.0,
+            },
+        ];
+        assert_eq!(9.0, offline_lower_bound(&two_tiers, &[4, 8, 12], 12));
+        assert_eq!(0.0, offline_lower_bound(&two_tiers, &[], 12));
+    }
+    #[test]
+    fn all_keep_sequence_costs_the_same_as_always_keeping() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let tiers = [
+            Tier {
+                time_cost: 2.0,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: 3.0,
+            },
+        ];
+        let access_list = vec![4, 8, 12];
+        let mut sim = Simulator::new(access_list.clone(), two_tier::AlwaysKeepInstance::new(2));
+        sim.run(20);
+        let policies = vec![Policy::Keep; 20];
+        assert_eq!(
+            sim.node.total_accrued_cost(),
+            cost_of_policy_sequence(&policies, &access_list, &tiers, 20)
+        );
+
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 6.0,
+        };
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..20 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 60, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let optimal = OptimalOffline::three_tier(&costs, &access_list, num_ticks);
+            assert_eq!(
+                optimal.minimum_cost(),
+                cost_of_policy_sequence(
+                    optimal.policies(),
+                    &access_list,
+                    &costs.tiers(),
+                    num_ticks
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Per-run experiment results and their export for downstream analysis.
+use crate::{Algorithm, Simulator};
+use std::io::{self, Write};
+
+/// Costs of a single run of an online instance against an offline instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunReport {
+    pub run_index: usize,
+    pub access_count: usize,
+    pub offline_cost: f64,
+    pub online_cost: f64,
+    pub competitive_ratio: f64,
+}
+
+impl RunReport {
+    /// Runs an online and an offline instance over the same access list for
+    /// `num_ticks` ticks.
+    pub fn new<A: Algorithm, B: Algorithm>(
+        run_index: usize,
+        online: A,
+        offline: B,
+        access_list: &[u64],
+        num_ticks: u64,
+    ) -> Self {
+        let result = SimulationResult::new(online, offline, access_list.to_vec(), num_ticks);
+        Self {
+            run_index,
+            access_count: result.access_list.len(),
+            offline_cost: result.offline_cost,
+            online_cost: result.online_cost,
+            competitive_ratio: result.competitive_ratio,
+        }
+    }
+}
+
+/// Outcome of a simulation along with the access list it ran on, so that it can be
+/// persisted and reproduced.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationResult {
+    pub offline_cost: f64,
+    pub online_cost: f64,
+    pub competitive_ratio: f64,
+    pub access_list: Vec<u64>,
+}
+
+impl SimulationResult {
+    /// Runs an online and an offline instance over the same access list for
+    /// `num_ticks` ticks.
+    pub fn new<A: Algorithm, B: Algorithm>(
+        online: A,
+        offline: B,
+        access_list: Vec<u64>,
+        num_ticks: u64,
+    ) -> Self {
+        let mut sim = Simulator::new(access_list.clone(), offline);
+        sim.run(num_ticks);
+        let offline_cost = sim.node.total_accrued_cost();
+        let mut sim = Simulator::new(access_list.clone(), online);
+        sim.run(num_ticks);
+        let online_cost = sim.node.total_accrued_cost();
+        Self {
+            offline_cost,
+            online_cost,
+            competitive_ratio: crate::competitive_ratio(online_cost, offline_cost),
+            access_list,
+        }
+    }
+}
+
+/// Writes the reports as CSV, one row per run after a single header row.
+pub fn write_csv<W: Write>(mut writer: W, reports: &[RunReport]) -> io::Result<()> {
+    writeln!(
+        writer,
+        "run_index,access_count,offline_cost,online_cost,competitive_ratio"
+    )?;
+    for report in reports {
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            report.run_index,
+            report.access_count,
+            report.offline_cost,
+            report.online_cost,
+            report.competitive_ratio
+        )?;
+    }
+    writer.flush()
+}
+
+/// Buckets the ratios into `bins` equal-width bins spanning their minimum to maximum,
+/// returned as `(bin_low, bin_high, count)` in increasing order. Each bin includes its
+/// low edge, and the last bin also includes the maximum. If every ratio is equal, they
+/// all fall into the first bin. Returns no bins for no ratios.
+///
+/// # Panics
+///
+/// Panics if `bins` is zero.
+pub fn histogram(ratios: &[f64], bins: usize) -> Vec<(f64, f64, usize)> {
+    assert!(bins > 0, "a histogram needs at least one bin");
+    if ratios.is_empty() {
+        return vec![];
+    }
+    let min = ratios.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = ratios.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for &ratio in ratios {
+        let bin = if width > 0.0 {
+            (((ratio - min) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let high = if i == bins - 1 {
+                max
+            } else {
+                min + width * (i + 1) as f64
+            };
+            (min + width * i as f64, high, count)
+        })
+        .collect()
+}
+
+/// Writes a histogram as a gnuplot data file, one whitespace-separated
+/// `bin_low bin_high count` row per bin after a commented header, e.g. for plotting
+/// with `plot "ratios.dat" using (($1 + $2) / 2):3 with boxes`.
+pub fn write_histogram<W: Write>(mut writer: W, histogram: &[(f64, f64, usize)]) -> io::Result<()> {
+    writeln!(writer, "# bin_low bin_high count")?;
+    for (low, high, count) in histogram {
+        writeln!(writer, "{} {} {}", low, high, count)?;
+    }
+    writer.flush()
+}
+
+/// Serializes the results as a pretty-printed JSON array.
+#[cfg(feature = "serde")]
+pub fn to_json(results: &[SimulationResult]) -> String {
+    serde_json::to_string_pretty(results).expect("simulation results serialize to JSON")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::two_tier::{NaiveInstance, OfflineInstance};
+    #[test]
+    fn csv_has_header_and_one_row_per_run() {
+        let reports: Vec<RunReport> = [vec![4, 8, 12], vec![2, 3]]
+            .iter()
+            .enumerate()
+            .map(|(run_index, access_list)| {
+                let offline =
+                    OfflineInstance::new(1, 3, access_list.clone().into_iter().peekable());
+                let num_ticks = *access_list.last().unwrap();
+                RunReport::new(
+                    run_index,
+                    NaiveInstance::new(1, 3),
+                    offline,
+                    access_list,
+                    num_ticks,
+                )
+            })
+            .collect();
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &reports).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            vec![
+                "run_index,access_count,offline_cost,online_cost,competitive_ratio",
+                "0,3,9,15,1.6666666666666667",
+                "1,2,1,1,1",
+            ],
+            lines
+        );
+    }
+    #[test]
+    fn histogram_counts_every_ratio() {
+        let ratios: Vec<f64> = (0..100).map(|i| 1.0 + i as f64 / 100.0).collect();
+        let bins = histogram(&ratios, 4);
+        assert_eq!(4, bins.len());
+        assert_eq!(
+            ratios.len(),
+            bins.iter().map(|&(_, _, count)| count).sum::<usize>()
+        );
+        assert_eq!(1.0, bins[0].0);
+        assert_eq!(1.99, bins[3].1);
+        assert!(bins.windows(2).all(|w| w[0].1 == w[1].0));
+        assert_eq!(vec![(1.5, 1.5, 3)], histogram(&[1.5, 1.5, 1.5], 1));
+
+        let mut data = Vec::new();
+        write_histogram(&mut data, &histogram(&[1.0, 2.0], 2)).unwrap();
+        assert_eq!(
+            "# bin_low bin_high count\n1 1.5 1\n1.5 2 1\n",
+            String::from_utf8(data).unwrap()
+        );
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn simulation_result_json_round_trip() {
+        let access_list = vec![4, 8, 12];
+        let offline = OfflineInstance::new(1, 3, access_list.clone().into_iter().peekable());
+        let result = SimulationResult::new(NaiveInstance::new(1, 3), offline, access_list, 12);
+        let json = to_json(std::slice::from_ref(&result));
+        let parsed: Vec<SimulationResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(vec![result], parsed);
+    }
+}
@@ -0,0 +1,121 @@
+//! Self-contained two-tier simulations that produce everything a front end needs to
+//! display in one call. The browser bindings in the `wasm` feature wrap these.
+use crate::analysis::cost_trajectory;
+use crate::two_tier::{
+    calculate_competitive_ratio, KarlinInstance, NaiveInstance, OfflineInstance,
+};
+use crate::util::{generate_access_list_with_rng, normalize_access_list, trim_access_list};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Competitive ratios of the deterministic and randomized two-tier algorithms
+/// along with the cumulative cost of each instance after every tick.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SimulationOutput {
+    pub deterministic_ratio: f64,
+    pub randomized_ratio: f64,
+    pub offline_trace: Vec<f64>,
+    pub deterministic_trace: Vec<f64>,
+    pub randomized_trace: Vec<f64>,
+}
+
+/// Simulates the two-tier problem over the given access list, seeding the
+/// randomized algorithm so the same inputs always produce the same output. The
+/// access list is sorted and deduplicated first, and accesses at tick 0 move to tick 1.
+pub fn simulate(
+    keep_cost: u64,
+    recover_cost: u64,
+    access_list: &[u32],
+    seed: u64,
+) -> SimulationOutput {
+    let access_list: Vec<u64> = access_list.iter().map(|&t| t as u64).collect();
+    let access_list = normalize_access_list(&access_list, u64::MAX);
+    let num_ticks = access_list.last().copied().unwrap_or(0);
+    let offline = OfflineInstance::new(
+        keep_cost,
+        recover_cost,
+        trim_access_list(&access_list, num_ticks)
+            .to_vec()
+            .into_iter()
+            .peekable(),
+    );
+    let deterministic = NaiveInstance::new(keep_cost, recover_cost);
+    let randomized = KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(seed));
+    SimulationOutput {
+        deterministic_ratio: calculate_competitive_ratio(
+            deterministic.clone(),
+            keep_cost,
+            recover_cost,
+            &access_list,
+            num_ticks,
+        ),
+        randomized_ratio: calculate_competitive_ratio(
+            randomized.clone(),
+            keep_cost,
+            recover_cost,
+            &access_list,
+            num_ticks,
+        ),
+        offline_trace: cost_trajectory(offline, access_list.clone(), num_ticks),
+        deterministic_trace: cost_trajectory(deterministic, access_list.clone(), num_ticks),
+        randomized_trace: cost_trajectory(randomized, access_list, num_ticks),
+    }
+}
+
+/// Number of accesses in the workload generated by [`simulate_random_workload`].
+pub const DEMO_ACCESSES: usize = 20;
+/// Latest tick an access can land on in the workload generated by
+/// [`simulate_random_workload`].
+pub const DEMO_TICKS: u64 = 200;
+
+/// Generates a random workload of [`DEMO_ACCESSES`] accesses within [`DEMO_TICKS`]
+/// ticks from the rng, then hands the rng to the randomized algorithm and returns its
+/// competitive ratio on that workload.
+pub fn simulate_random_workload<R: Rng>(keep_cost: u64, recover_cost: u64, mut rng: R) -> f64 {
+    let access_list = generate_access_list_with_rng(DEMO_ACCESSES, DEMO_TICKS, &mut rng);
+    let num_ticks = access_list.last().copied().unwrap_or(0);
+    let online = KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+    calculate_competitive_ratio(online, keep_cost, recover_cost, access_list, num_ticks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn matches_native_api() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let seed = 42;
+        let output = simulate(keep_cost, recover_cost, &[4, 8, 12], seed);
+
+        let access_list = vec![4, 8, 12];
+        let online = NaiveInstance::new(keep_cost, recover_cost);
+        let deterministic_ratio =
+            calculate_competitive_ratio(online, keep_cost, recover_cost, access_list.clone(), 12);
+        let online = KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(seed));
+        let randomized_trace = cost_trajectory(online.clone(), access_list.clone(), 12);
+        let randomized_ratio =
+            calculate_competitive_ratio(online, keep_cost, recover_cost, access_list, 12);
+
+        assert_eq!(deterministic_ratio, output.deterministic_ratio);
+        assert_eq!(randomized_ratio, output.randomized_ratio);
+        assert_eq!(randomized_trace, output.randomized_trace);
+        assert_eq!(12, output.offline_trace.len());
+    }
+    #[test]
+    fn unsorted_access_lists_are_normalized() {
+        let output = simulate(1, 3, &[12, 0, 8, 8, 4], 42);
+        assert_eq!(simulate(1, 3, &[1, 4, 8, 12], 42), output);
+    }
+    #[test]
+    fn random_workload_is_finite_and_reproducible() {
+        let ratio = simulate_random_workload(1, 3, StdRng::seed_from_u64(42));
+        assert!(ratio.is_finite());
+        assert!(ratio >= 1.0);
+        assert_eq!(
+            ratio,
+            simulate_random_workload(1, 3, StdRng::seed_from_u64(42))
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Drives a two-tier online algorithm from a stream of access ticks, e.g. piped in
+//! from an external trace tool, reporting its costs as each access arrives.
+use crate::{competitive_ratio, Algorithm};
+use std::io::{self, BufRead, Write};
+
+/// Reads one access tick per line from `reader`, ticking `instance` up to and
+/// including each access, and writes a line per access with the online cost so far,
+/// the offline optimum's cost so far and their ratio. Blank lines are skipped.
+///
+/// The offline optimum cannot be run ahead of the stream, but its cost up to the
+/// latest access is known exactly once that access arrives: it keeps or discards over
+/// each gap, whichever is cheaper, and only needs to know where the gap ends. The
+/// reported ratio is therefore exact for the prefix of the stream read so far.
+///
+/// Returns an `InvalidData` error on a line that is not a tick, or a tick at or before
+/// the previous one.
+pub fn run_stream<R: BufRead, W: Write, T: Algorithm>(
+    reader: R,
+    mut writer: W,
+    mut instance: T,
+    keep_cost: u64,
+    recover_cost: u64,
+) -> io::Result<()> {
+    let mut t = 0;
+    let mut offline_cost = 0.0;
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let access: u64 = line.parse().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid tick {:?}: {}", line, err),
+            )
+        })?;
+        if access <= t {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("tick {} does not come after tick {}", access, t),
+            ));
+        }
+        let idle = access - t - 1;
+        offline_cost += (keep_cost * idle).min(recover_cost) as f64;
+        while t + 1 < access {
+            t += 1;
+            instance.tick(false);
+        }
+        t = access;
+        instance.tick(true);
+        let online_cost = instance.total_accrued_cost();
+        writeln!(
+            writer,
+            "tick {}: online={}, offline={}, ratio={:.2}",
+            t,
+            online_cost,
+            offline_cost,
+            competitive_ratio(online_cost, offline_cost)
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::two_tier::{calculate_competitive_ratio, NaiveInstance};
+    use std::io::Cursor;
+    #[test]
+    fn stream_matches_batch_run() {
+        let input = Cursor::new("4\n8\n\n12\n");
+        let mut output = Vec::new();
+        run_stream(input, &mut output, NaiveInstance::new(1, 3), 1, 3).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            vec![
+                "tick 4: online=5, offline=3, ratio=1.67",
+                "tick 8: online=10, offline=6, ratio=1.67",
+                "tick 12: online=15, offline=9, ratio=1.67",
+            ],
+            output.lines().collect::<Vec<_>>()
+        );
+        let ratio = calculate_competitive_ratio(NaiveInstance::new(1, 3), 1, 3, vec![4, 8, 12], 12);
+        assert_eq!(15.0 / 9.0, ratio);
+    }
+    #[test]
+    fn invalid_ticks_are_rejected() {
+        for input in ["4\nfour\n", "4\n4\n", "4\n2\n"] {
+            let err = run_stream(
+                Cursor::new(input),
+                io::sink(),
+                NaiveInstance::new(1, 3),
+                1,
+                3,
+            )
+            .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        }
+    }
+}
@@ -1,9 +1,16 @@
-use crate::{karlin, Algorithm, Policy};
+use crate::n_tier::{self, Tier, TierError};
+use crate::util::{validate_access_list, AccessListError};
+use crate::{Algorithm, Cost, CostBreakdown, Policy};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::fmt;
 use std::iter::Peekable;
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     #[test]
     fn three_tier_instance() {
         let costs = Costs {
@@ -12,16 +19,350 @@ mod test {
             recover_from_compressed_cost: 2.0,
             recover_from_discard_cost: 3.0,
         };
-        let access_list = vec![4, 8, 12, 16, 20];
-        let online = KarlinInstance::new(costs.clone());
-        let num_ticks = 20;
-        let ratio = calculate_competitive_ratio(online, costs, access_list, num_ticks);
-        eprintln!("{}", ratio);
-        //assert!(ratio < 1.0);
+        let access_list = vec![4, 8, 12];
+        let num_ticks = 12;
+        // Each access follows 3 idle ticks, over which keeping costs 3, compressing
+        // 0.5 * 3 + 2 = 3.5 and discarding 3, so the offline pays 3 per access.
+        let offline =
+            OfflineInstance::new(costs.clone(), access_list.clone().into_iter().peekable())
+                .unwrap();
+        let mut sim = crate::Simulator::new(access_list.clone(), offline);
+        sim.run(num_ticks);
+        assert_eq!(9.0, sim.node.total_accrued_cost());
+        assert_eq!(
+            Ok(9.0),
+            offline_cost(costs.clone(), &[4, 8, 12, 20], num_ticks)
+        );
+        // Past the last access the object is discarded for free.
+        assert_eq!(Ok(9.0), offline_cost(costs.clone(), &access_list, 30));
+        // Whatever thresholds it samples, the online instance pays at most the keep
+        // cost of every idle tick plus the discard recovery cost, 3 + 3 per access,
+        // bounding the ratio by 2.
+        let rng = StdRng::seed_from_u64(0);
+        let online = KarlinInstance::with_rng(costs.clone(), rng).unwrap();
+        let ratio = calculate_competitive_ratio(online, costs, access_list, num_ticks).unwrap();
+        assert!((1.0..=2.0).contains(&ratio));
+    }
+    #[test]
+    fn offline_never_exceeds_online() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let online =
+                KarlinInstance::with_rng(costs.clone(), StdRng::seed_from_u64(rng.gen())).unwrap();
+            let ratio =
+                calculate_competitive_ratio(online, costs.clone(), access_list.clone(), num_ticks)
+                    .unwrap();
+            assert!(ratio >= 1.0, "online beat offline on {:?}", access_list);
+        }
+    }
+    #[test]
+    fn empirical_ratio_below_theoretical_bound() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        let bound = theoretical_ratio_bound(&costs);
+        let trials = 1_000;
+        let mut total = 0.0;
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..trials {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let online = KarlinInstance::with_rng(costs.clone(), &mut rng).unwrap();
+            total +=
+                calculate_competitive_ratio(online, costs.clone(), access_list, num_ticks).unwrap();
+        }
+        assert!(total / trials as f64 <= bound);
+    }
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        let run = || {
+            let mut rng = StdRng::seed_from_u64(42);
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let mut sim = crate::Simulator::new(
+                access_list,
+                KarlinInstance::with_rng(costs.clone(), rng).unwrap(),
+            );
+            sim.run_to_completion();
+            sim.node.total_accrued_cost()
+        };
+        assert_eq!(run(), run());
+    }
+    #[test]
+    fn long_idle_period_ends_in_discard() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        for seed in 0..20 {
+            let online = KarlinInstance::with_rng(costs.clone(), StdRng::seed_from_u64(seed));
+            let mut sim = crate::Simulator::new(vec![100], online.unwrap());
+            let trace = sim.run_with_trace(99);
+            // The discard threshold is at most the discard recovery cost, so by then the
+            // object has left the compressed tier and stops accruing cost.
+            let settled = costs.recover_from_discard_cost as usize;
+            assert!(trace[settled..]
+                .iter()
+                .all(|record| record.policy == Policy::Discard && record.delta_cost == 0.0));
+        }
+    }
+    #[test]
+    fn offline_keeps_when_compressing_never_pays() {
+        // Compressing saves only 0.25 per tick for a recovery of 6, so it never beats
+        // both keeping and discarding; over a single idle tick keeping costs 3 and
+        // discarding 7. Comparing the thresholds pairwise discarded there, paying 28.
+        let costs = Costs {
+            keep_time_cost: 3.0,
+            compressed_time_cost: 2.75,
+            recover_from_compressed_cost: 6.0,
+            recover_from_discard_cost: 7.0,
+        };
+        let access_list = vec![2, 3, 25, 48, 53];
+        let offline =
+            OfflineInstance::new(costs.clone(), access_list.clone().into_iter().peekable())
+                .unwrap();
+        let mut sim = crate::Simulator::new(access_list.clone(), offline);
+        sim.run(53);
+        let optimal = crate::optimal_offline::OptimalOffline::three_tier(&costs, &access_list, 53);
+        assert_eq!(24.0, sim.node.total_accrued_cost());
+        assert_eq!(optimal.minimum_cost(), sim.node.total_accrued_cost());
+    }
+    #[test]
+    fn thresholds_scale_with_keep_cost() {
+        // Over an idle gap of y ticks keeping costs 2y, compressing y + 2 and discarding
+        // 6, so the offline keeps through 1 idle tick, compresses through 3 and
+        // discards through 5, paying 2 + 5 + 6.
+        let costs = Costs {
+            keep_time_cost: 2.0,
+            compressed_time_cost: 1.0,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 6.0,
+        };
+        let access_list = vec![2, 6, 12];
+        let offline =
+            OfflineInstance::new(costs.clone(), access_list.clone().into_iter().peekable())
+                .unwrap();
+        let mut sim = crate::Simulator::new(access_list.clone(), offline);
+        let policies: Vec<Policy> = sim
+            .run_with_trace(12)
+            .iter()
+            .map(|record| record.policy)
+            .collect();
+        assert_eq!(13.0, sim.node.total_accrued_cost());
+        assert_eq!(
+            [
+                Policy::Keep,
+                Policy::Keep,
+                Policy::Compress,
+                Policy::Compress,
+                Policy::Compress,
+                Policy::Keep,
+                Policy::Discard
+            ],
+            policies[..7]
+        );
+        // The online instance pays keep cost until the sampled threshold, which is at
+        // most rd, so it discards on the idle tick after rd / keep ticks at the latest.
+        let online = KarlinInstance::with_rng(costs.clone(), StdRng::seed_from_u64(1)).unwrap();
+        let mut sim = crate::Simulator::new(vec![100], online);
+        sim.run(4);
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+    }
+    #[test]
+    fn invalid_costs_are_rejected() {
+        let valid = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 3.0,
+        };
+        let expensive_compression = Costs {
+            compressed_time_cost: 1.0,
+            ..valid.clone()
+        };
+        assert_eq!(
+            Some(CostError::CompressedTimeCostTooHigh {
+                compressed_time_cost: 1.0,
+                keep_time_cost: 1.0,
+            }),
+            KarlinInstance::new(expensive_compression.clone()).err()
+        );
+        assert_eq!(
+            Some(CostError::CompressedTimeCostTooHigh {
+                compressed_time_cost: 1.0,
+                keep_time_cost: 1.0,
+            }),
+            OfflineInstance::new(expensive_compression, vec![4].into_iter().peekable()).err()
+        );
+        let expensive_recovery = Costs {
+            recover_from_compressed_cost: 3.0,
+            ..valid.clone()
+        };
+        let expected = CostError::CompressedRecoveryNotCheaper {
+            recover_from_compressed_cost: 3.0,
+            recover_from_discard_cost: 3.0,
+        };
+        assert_eq!(
+            Some(expected.clone()),
+            KarlinInstance::new(expensive_recovery.clone()).err()
+        );
+        assert_eq!(
+            Some(expected),
+            OfflineInstance::new(expensive_recovery, vec![4].into_iter().peekable()).err()
+        );
+        assert!(KarlinInstance::new(valid).is_ok());
+    }
+    #[test]
+    fn costs_builder() {
+        let builder = CostsBuilder::new()
+            .keep_time_cost(1.0)
+            .compressed_time_cost(0.5)
+            .recover_from_compressed_cost(2.0)
+            .recover_from_discard_cost(3.0);
+        let costs = builder.clone().build().unwrap();
+        assert_eq!(
+            (1.0, 0.5, 2.0, 3.0),
+            (
+                costs.keep_time_cost,
+                costs.compressed_time_cost,
+                costs.recover_from_compressed_cost,
+                costs.recover_from_discard_cost
+            )
+        );
+        let integer = CostsBuilder::new()
+            .keep_time_cost(2u64)
+            .compressed_time_cost(1u64)
+            .recover_from_compressed_cost(4u64)
+            .recover_from_discard_cost(6u64);
+        let integer_costs = integer.build().unwrap();
+        assert_eq!(
+            (2.0, 1.0, 4.0, 6.0),
+            (
+                integer_costs.keep_time_cost,
+                integer_costs.compressed_time_cost,
+                integer_costs.recover_from_compressed_cost,
+                integer_costs.recover_from_discard_cost
+            )
+        );
+        assert_eq!(
+            Err(CostError::CompressedTimeCostTooHigh {
+                compressed_time_cost: 1.5,
+                keep_time_cost: 1.0,
+            }),
+            builder
+                .clone()
+                .compressed_time_cost(1.5)
+                .build()
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(CostError::CompressedRecoveryNotCheaper {
+                recover_from_compressed_cost: 3.0,
+                recover_from_discard_cost: 3.0,
+            }),
+            builder
+                .clone()
+                .recover_from_compressed_cost(3.0)
+                .build()
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(CostError::Tiers(TierError::Unordered { index: 2 })),
+            builder
+                .clone()
+                .compressed_time_cost(-0.5)
+                .build()
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(CostError::MissingCost {
+                name: "recover_from_discard_cost"
+            }),
+            CostsBuilder::new()
+                .keep_time_cost(1.0)
+                .compressed_time_cost(0.5)
+                .recover_from_compressed_cost(2.0)
+                .build()
+                .map(|_| ())
+        );
+    }
+    #[test]
+    fn fractional_costs_are_not_truncated() {
+        // Every gap has 11 idle ticks, over which compressing costs 0.5 * 11 + 2 = 7.5,
+        // below keeping for 11 or discarding for 30.
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 2.0,
+            recover_from_discard_cost: 30.0,
+        };
+        let access_list: Vec<u64> = (1..=101).map(|i| i * 12).collect();
+        let num_ticks = *access_list.last().unwrap();
+        let offline =
+            OfflineInstance::new(costs.clone(), access_list.clone().into_iter().peekable())
+                .unwrap();
+        let mut sim = crate::Simulator::new(access_list, offline);
+        sim.run(num_ticks);
+        let total = sim.node.total_accrued_cost();
+        assert_eq!(757.5, total);
+        assert_ne!(total.trunc(), total);
+    }
+    #[test]
+    fn thresholds_are_resampled_within_costs() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 4.0,
+            recover_from_discard_cost: 10.0,
+        };
+        let mut online = KarlinInstance::with_rng(costs, StdRng::seed_from_u64(3)).unwrap();
+        for t in 1..=200 {
+            online.tick(t % 7 == 0);
+            let compress = online.current_compress_threshold();
+            let discard = online.current_discard_threshold();
+            assert!((0..=4).contains(&compress));
+            assert!((compress..=10).contains(&discard));
+        }
+    }
+    #[test]
+    fn rejects_invalid_access_lists() {
+        let costs = Costs {
+            keep_time_cost: 1.0,
+            compressed_time_cost: 0.5,
+            recover_from_compressed_cost: 5.0,
+            recover_from_discard_cost: 10.0,
+        };
+        let online = KarlinInstance::new(costs.clone()).unwrap();
+        assert_eq!(
+            Err(CostError::AccessList(AccessListError::OutOfRange {
+                tick: 0,
+                num_ticks: u64::MAX
+            })),
+            calculate_competitive_ratio(online, costs, vec![0, 3], 10)
+        );
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Costs {
     pub keep_time_cost: f64,
     pub compressed_time_cost: f64,
@@ -29,185 +370,344 @@ pub struct Costs {
     pub recover_from_discard_cost: f64,
 }
 
+impl Costs {
+    /// Checks the invariants that make compression a meaningful middle tier.
+    pub fn validate(&self) -> Result<(), CostError> {
+        // The cost to keep compressed data is less than the normal keep cost.
+        if self.compressed_time_cost >= self.keep_time_cost {
+            return Err(CostError::CompressedTimeCostTooHigh {
+                compressed_time_cost: self.compressed_time_cost,
+                keep_time_cost: self.keep_time_cost,
+            });
+        }
+        // Recovering from a discard is more expensive than from a compressed state.
+        if self.recover_from_compressed_cost >= self.recover_from_discard_cost {
+            return Err(CostError::CompressedRecoveryNotCheaper {
+                recover_from_compressed_cost: self.recover_from_compressed_cost,
+                recover_from_discard_cost: self.recover_from_discard_cost,
+            });
+        }
+        Ok(())
+    }
+    /// The keep, compress and discard tiers as an N-tier hierarchy.
+    pub fn tiers(&self) -> Vec<Tier> {
+        vec![
+            Tier {
+                time_cost: self.keep_time_cost,
+                recover_cost: 0.0,
+            },
+            Tier {
+                time_cost: self.compressed_time_cost,
+                recover_cost: self.recover_from_compressed_cost,
+            },
+            Tier {
+                time_cost: 0.0,
+                recover_cost: self.recover_from_discard_cost,
+            },
+        ]
+    }
+}
+
+/// Builds [`Costs`] one named cost at a time, checking that all four were given and
+/// that they satisfy the three-tier invariants.
+#[derive(Debug, Clone, Default)]
+pub struct CostsBuilder {
+    keep_time_cost: Option<f64>,
+    compressed_time_cost: Option<f64>,
+    recover_from_compressed_cost: Option<f64>,
+    recover_from_discard_cost: Option<f64>,
+}
+
+impl CostsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn keep_time_cost<C: Cost>(mut self, cost: C) -> Self {
+        self.keep_time_cost = Some(cost.to_f64());
+        self
+    }
+    pub fn compressed_time_cost<C: Cost>(mut self, cost: C) -> Self {
+        self.compressed_time_cost = Some(cost.to_f64());
+        self
+    }
+    pub fn recover_from_compressed_cost<C: Cost>(mut self, cost: C) -> Self {
+        self.recover_from_compressed_cost = Some(cost.to_f64());
+        self
+    }
+    pub fn recover_from_discard_cost<C: Cost>(mut self, cost: C) -> Self {
+        self.recover_from_discard_cost = Some(cost.to_f64());
+        self
+    }
+    pub fn build(self) -> Result<Costs, CostError> {
+        let require = |cost: Option<f64>, name| cost.ok_or(CostError::MissingCost { name });
+        let costs = Costs {
+            keep_time_cost: require(self.keep_time_cost, "keep_time_cost")?,
+            compressed_time_cost: require(self.compressed_time_cost, "compressed_time_cost")?,
+            recover_from_compressed_cost: require(
+                self.recover_from_compressed_cost,
+                "recover_from_compressed_cost",
+            )?,
+            recover_from_discard_cost: require(
+                self.recover_from_discard_cost,
+                "recover_from_discard_cost",
+            )?,
+        };
+        costs.validate()?;
+        n_tier::validate_tiers(&costs.tiers())?;
+        Ok(costs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CostError {
+    /// A cost was never given to the [`CostsBuilder`].
+    MissingCost { name: &'static str },
+    /// Keeping compressed data costs at least as much per tick as keeping it as is.
+    CompressedTimeCostTooHigh {
+        compressed_time_cost: f64,
+        keep_time_cost: f64,
+    },
+    /// Recovering from compression costs at least as much as recovering from a discard.
+    CompressedRecoveryNotCheaper {
+        recover_from_compressed_cost: f64,
+        recover_from_discard_cost: f64,
+    },
+    /// The costs do not form an ordered hierarchy, e.g. because one of them is negative.
+    Tiers(TierError),
+    /// The access list is not sorted and distinct, or accesses tick 0.
+    AccessList(AccessListError),
+}
+
+impl fmt::Display for CostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostError::CompressedTimeCostTooHigh {
+                compressed_time_cost,
+                keep_time_cost,
+            } => write!(
+                f,
+                "compressed time cost {} must be below the keep time cost {}",
+                compressed_time_cost, keep_time_cost
+            ),
+            CostError::CompressedRecoveryNotCheaper {
+                recover_from_compressed_cost,
+                recover_from_discard_cost,
+            } => write!(
+                f,
+                "recovering from compression ({}) must cost less than recovering from a discard ({})",
+                recover_from_compressed_cost, recover_from_discard_cost
+            ),
+            CostError::MissingCost { name } => write!(f, "{} was not set", name),
+            CostError::Tiers(err) => err.fmt(f),
+            CostError::AccessList(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CostError {}
+
+impl From<TierError> for CostError {
+    fn from(err: TierError) -> Self {
+        CostError::Tiers(err)
+    }
+}
+
+impl From<AccessListError> for CostError {
+    fn from(err: AccessListError) -> Self {
+        CostError::AccessList(err)
+    }
+}
+
+/// Randomized algorithm that compresses and then discards once the keep cost paid since
+/// the last access reaches coupled Karlin thresholds, resampled after every access.
 #[derive(Debug, Clone)]
-pub struct KarlinInstance {
-    t: u64,
-    accrued_cost: f64,
-    costs: Costs,
-    policy: Policy,
-    last_access: u64,
-    t_to_wait_before_discard: u64,
-    t_to_wait_before_compress: u64,
+pub struct KarlinInstance<R: Rng = ThreadRng> {
+    inner: n_tier::KarlinInstance<R>,
 }
 
 impl KarlinInstance {
-    pub fn new(costs: Costs) -> KarlinInstance {
-        // The cost to keep compressed data is less than the normal keep cost.
-        assert!(costs.compressed_time_cost < 1.0);
-        // Recovering from a discard is more expensive than from a compressed state.
-        assert!(costs.recover_from_compressed_cost < costs.recover_from_discard_cost);
-        let cc = costs.recover_from_compressed_cost;
-        let dc = costs.recover_from_discard_cost;
-        Self {
-            t: 0,
-            costs,
-            accrued_cost: 0.0,
-            policy: Policy::Keep,
-            last_access: 0,
-            t_to_wait_before_discard: karlin::sample(dc as u64),
-            t_to_wait_before_compress: karlin::sample(cc as u64),
-        }
+    pub fn new(costs: Costs) -> Result<KarlinInstance, CostError> {
+        Self::with_rng(costs, thread_rng())
+    }
+}
+
+impl<R: Rng> KarlinInstance<R> {
+    /// Creates an instance that samples its thresholds from the provided rng, so that
+    /// runs can be reproduced from a seed.
+    pub fn with_rng(costs: Costs, rng: R) -> Result<Self, CostError> {
+        costs.validate()?;
+        Ok(Self {
+            inner: n_tier::KarlinInstance::with_rng(costs.tiers(), rng)?,
+        })
+    }
+    /// Keep cost paid since the last access at which the object is compressed, as
+    /// sampled after that access. Thresholds are whole numbers, since the recovery
+    /// costs are truncated for sampling.
+    pub fn current_compress_threshold(&self) -> u64 {
+        self.inner.thresholds()[1] as u64
+    }
+    /// Keep cost paid since the last access at which the object is discarded, as
+    /// sampled after that access.
+    pub fn current_discard_threshold(&self) -> u64 {
+        self.inner.thresholds()[2] as u64
     }
 }
 
-impl Algorithm for KarlinInstance {
+impl<R: Rng> Algorithm for KarlinInstance<R> {
     fn tick(&mut self, access: bool) {
-        self.t += 1;
-        // Check if we need to change our policy. Should only do this if
-        // we are in keep mode for the instance.
-        if matches!(self.policy, Policy::Keep) {
-            let time_elapsed = self.t - self.last_access;
-            let should_discard = time_elapsed >= self.t_to_wait_before_discard;
-            let should_compress = time_elapsed >= self.t_to_wait_before_compress;
-            if should_discard {
-                self.policy = Policy::Discard;
-            } else if should_compress {
-                self.policy = Policy::Compress;
-            }
-        }
-        // if no access, charge normal time costs if applicable.
-        if !access {
-            match self.policy {
-                Policy::Keep => self.accrued_cost += self.costs.keep_time_cost,
-                Policy::Compress => self.accrued_cost += self.costs.compressed_time_cost,
-                Policy::Discard => {}
-            }
-            return;
-        }
-        self.last_access = self.t;
-        self.t_to_wait_before_discard = karlin::sample(self.costs.recover_from_discard_cost as u64);
-        self.t_to_wait_before_compress =
-            karlin::sample(self.costs.recover_from_compressed_cost as u64);
-
-        // Incur a recovery cost if necessary.
-        match self.policy {
-            Policy::Compress => self.accrued_cost += self.costs.recover_from_compressed_cost,
-            Policy::Discard => self.accrued_cost += self.costs.recover_from_discard_cost,
-            Policy::Keep => {}
-        }
-        self.policy = Policy::Keep;
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
     }
     fn total_accrued_cost(&self) -> f64 {
-        self.accrued_cost
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
     }
 }
 
+/// Omniscient algorithm, which holds the object over each idle gap in whichever of the
+/// three tiers is cheapest until the next access; see [`n_tier::OfflineInstance`].
 #[derive(Debug, Clone)]
 pub struct OfflineInstance<T>
 where
     T: Iterator<Item = u64>,
 {
-    t: u64,
-    access_list: Peekable<T>,
-    accrued_cost: f64,
-    costs: Costs,
-    policy: Policy,
+    inner: n_tier::OfflineInstance<T>,
 }
 
 impl<T> OfflineInstance<T>
 where
-    T: Iterator<Item = u64>,
+    T: Iterator<Item = u64> + Clone,
 {
-    pub fn new(costs: Costs, access_list: Peekable<T>) -> OfflineInstance<T> {
-        // The cost to keep compressed data is less than the normal keep cost.
-        assert!(costs.compressed_time_cost < 1.0);
-        // Recovering from a discard is more expensive than from a compressed state.
-        assert!(costs.recover_from_compressed_cost < costs.recover_from_discard_cost);
-        Self {
-            t: 0,
-            access_list,
-            costs,
-            accrued_cost: 0.0,
-            policy: Policy::Keep,
-        }
+    pub fn new(costs: Costs, access_list: Peekable<T>) -> Result<OfflineInstance<T>, CostError> {
+        costs.validate()?;
+        Ok(Self {
+            inner: n_tier::OfflineInstance::new(costs.tiers(), access_list)?,
+        })
     }
 }
 
 impl<T> Algorithm for OfflineInstance<T>
 where
-    T: Iterator<Item = u64>,
+    T: Iterator<Item = u64> + Clone,
 {
     fn tick(&mut self, access: bool) {
-        self.t += 1;
-        let Some(next_access) = self.access_list.peek() else {
-            return;
-        };
-        // Check if we need to change our policy. Should only do this if
-        // we are in keep mode for the instance.
-        if matches!(self.policy, Policy::Keep) {
-            let next_access = *next_access as f64;
-            let keep_threshold =
-                self.costs.recover_from_compressed_cost / (1.0 - self.costs.compressed_time_cost);
-            if next_access <= keep_threshold {
-                self.policy = Policy::Keep;
-            }
-            let compress_threshold = (self.costs.recover_from_discard_cost
-                - self.costs.recover_from_compressed_cost)
-                / self.costs.compressed_time_cost;
-            if keep_threshold <= next_access && next_access <= compress_threshold {
-                self.policy = Policy::Compress;
-            }
-            if next_access > compress_threshold {
-                self.policy = Policy::Discard;
-            }
-        }
-        // if no access, charge normal time costs if applicable.
-        if !access {
-            match self.policy {
-                Policy::Keep => self.accrued_cost += self.costs.keep_time_cost,
-                Policy::Compress => self.accrued_cost += self.costs.compressed_time_cost,
-                Policy::Discard => {}
-            }
-            return;
-        }
-
-        // Advance the access list iterator.
-        let _ = self.access_list.next();
-
-        // Incur a recovery cost if necessary.
-        match self.policy {
-            Policy::Compress => self.accrued_cost += self.costs.recover_from_compressed_cost,
-            Policy::Discard => self.accrued_cost += self.costs.recover_from_discard_cost,
-            Policy::Keep => {}
-        }
-        self.policy = Policy::Keep;
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
     }
     fn total_accrued_cost(&self) -> f64 {
-        self.accrued_cost
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        1.0
     }
 }
 
-pub fn calculate_competitive_ratio<T: Algorithm>(
+pub fn calculate_competitive_ratio<T: Algorithm, L: AsRef<[u64]>>(
     instance: T,
     costs: Costs,
-    access_list: Vec<u64>,
+    access_list: L,
     num_ticks: u64,
-) -> f64 {
-    // Offline, omniscient instance.
-    let offline = OfflineInstance::new(costs, access_list.clone().into_iter().peekable());
-    let mut sim = crate::Simulator::new(access_list.clone(), offline);
-    for _ in 0..num_ticks {
-        sim.tick();
-    }
-    let offline_cost = sim.node.total_accrued_cost();
+) -> Result<f64, CostError> {
+    let access_list = access_list.as_ref();
+    let offline_cost = offline_cost(costs, access_list, num_ticks)?;
+    Ok(crate::online_ratio(
+        instance,
+        access_list,
+        num_ticks,
+        offline_cost,
+    ))
+}
 
-    // Online instance.
-    let mut sim = crate::Simulator::new(access_list, instance);
-    for _ in 0..num_ticks {
-        sim.tick();
-    }
-    let online_cost = sim.node.total_accrued_cost();
+/// Cost of the offline optimum over the access list for `num_ticks` ticks, which can
+/// be reused with [`crate::online_ratio`] across online instances. Accesses after
+/// `num_ticks` are ignored; see [`crate::util::trim_access_list`]. Fails if the access
+/// list is not sorted and distinct, or accesses tick 0; see [`validate_access_list`].
+pub fn offline_cost(costs: Costs, access_list: &[u64], num_ticks: u64) -> Result<f64, CostError> {
+    validate_access_list(access_list, u64::MAX)?;
+    let access_list = crate::util::trim_access_list(access_list, num_ticks);
+    let offline = OfflineInstance::new(costs, access_list.iter().copied().peekable())?;
+    let mut sim = crate::Simulator::new(access_list.iter().copied(), offline);
+    sim.run(num_ticks);
+    Ok(sim.node.total_accrued_cost())
+}
+
+/// Upper bound on the expected competitive ratio of the randomized three-tier algorithm.
+///
+/// For an idle gap of length y the optimum pays `min(keep * y, compressed * y + rc, rd)`.
+/// The online algorithm discards once the keep cost paid reaches its discard threshold
+/// Z ~ Karlin(rd), and compresses before that once it reaches its coupled compress
+/// threshold X = Z * rc / rd. Thresholds are resampled after every access, so the ratio
+/// over a whole access list is bounded by the worst ratio of expected online cost to
+/// optimal cost over a single gap, which we take here. Beyond y = rd / keep both
+/// thresholds have surely elapsed and the ratio can only decrease, so the search stops
+/// there.
+pub fn theoretical_ratio_bound(costs: &Costs) -> f64 {
+    let steps = 200;
+    let horizon = costs.recover_from_discard_cost / costs.keep_time_cost;
+    (1..=steps)
+        .map(|i| {
+            let y = horizon * i as f64 / steps as f64;
+            expected_gap_cost(costs, y) / optimal_gap_cost(costs, y)
+        })
+        .fold(1.0, f64::max)
+}
+
+fn optimal_gap_cost(costs: &Costs, y: f64) -> f64 {
+    (costs.keep_time_cost * y)
+        .min(costs.compressed_time_cost * y + costs.recover_from_compressed_cost)
+        .min(costs.recover_from_discard_cost)
+}
 
-    // Competitive ratio.
-    online_cost / offline_cost
+/// Expected online cost of an idle gap of length y, integrating over the discard
+/// threshold that determines both coupled thresholds.
+fn expected_gap_cost(costs: &Costs, y: f64) -> f64 {
+    let steps = 2_000;
+    let (k, cc) = (costs.keep_time_cost, costs.compressed_time_cost);
+    let (rc, rd) = (
+        costs.recover_from_compressed_cost,
+        costs.recover_from_discard_cost,
+    );
+    let e = std::f64::consts::E;
+    let density = |d: f64| (d / rd).exp() / ((e - 1.0) * rd);
+    let dz = rd / steps as f64;
+    (0..steps)
+        .map(|i| {
+            let d = (i as f64 + 0.5) * dz;
+            // Times at which each threshold elapses.
+            let z = d / k;
+            let x = z * rc / rd;
+            let gap_cost = if y < x {
+                // Neither threshold elapses during the gap.
+                k * y
+            } else if y < z {
+                // Compressed at x and recovered from compression.
+                k * x + cc * (y - x) + rc
+            } else {
+                // Compressed at x, then discarded at z.
+                k * x + cc * (z - x) + rd
+            };
+            density(d) * gap_cost * dz
+        })
+        .sum()
 }
@@ -1,105 +1,485 @@
-use crate::{karlin, Algorithm, Policy};
+use crate::karlin::{KarlinDistribution, WaitDistribution};
+use crate::n_tier::{self, Tier};
+use crate::util::{trim_access_list, validate_access_list};
+use crate::{karlin, Algorithm, Cost, CostBreakdown, Policy};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
 use std::iter::Peekable;
 
+/// Two-tier costs broken down along cloud storage pricing lines, where restoring an
+/// object from archival storage pays a fixed retrieval fee plus the egress of
+/// transferring it back out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloudCosts {
+    pub storage_cost: u64,
+    pub retrieval_fee: u64,
+    pub egress_cost: u64,
+}
+
+impl CloudCosts {
+    pub fn keep_cost(&self) -> u64 {
+        self.storage_cost
+    }
+    pub fn recover_cost(&self) -> u64 {
+        self.retrieval_fee + self.egress_cost
+    }
+}
+
+/// Converts a cost given in any [`Cost`] type, including signed ones.
+///
+/// # Panics
+///
+/// Panics if the cost is negative or NaN.
+fn non_negative<C: Cost>(cost: C) -> f64 {
+    let cost = cost.to_f64();
+    assert!(cost >= 0.0, "costs must be non-negative, got {}", cost);
+    cost
+}
+
+/// Keep and discard tiers of the two-tier problem, as an N-tier hierarchy.
+fn tiers<C: Cost>(keep_cost: C, recover_cost: C) -> Vec<Tier> {
+    vec![
+        Tier {
+            time_cost: non_negative(keep_cost),
+            recover_cost: 0.0,
+        },
+        Tier {
+            time_cost: 0.0,
+            recover_cost: non_negative(recover_cost),
+        },
+    ]
+}
+
+/// Tier of the two-tier hierarchy that holds an object under the given policy.
+///
+/// # Panics
+///
+/// Panics if the policy is `Compress`, since two tiers have no compressed tier.
+fn policy_tier(policy: Policy) -> usize {
+    match policy {
+        Policy::Keep => 0,
+        Policy::Discard => 1,
+        Policy::Compress => panic!("two tiers have no compressed tier to start in"),
+    }
+}
+
+/// Cost of recovering a discarded object, which may depend on how long it stayed
+/// discarded, e.g. when older data sinks into deeper archival tiers.
+///
+/// Only the online instances take a recovery cost model. The offline optimum, and so
+/// [`calculate_competitive_ratio`] and the competitive bounds, assume the constant
+/// cost of recovering right after a discard, so runs under other models are best
+/// compared by their costs rather than their ratios.
+pub trait RecoverCost {
+    /// Cost of recovering an object that was discarded `idle_ticks` ticks ago.
+    fn cost(&self, idle_ticks: u64) -> f64;
+}
+
+/// The same recovery cost however long the object was discarded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant(pub f64);
+
+impl RecoverCost for Constant {
+    fn cost(&self, _idle_ticks: u64) -> f64 {
+        self.0
+    }
+}
+
+/// A recovery cost that grows by `per_tick` for every tick the object was discarded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Linear {
+    pub base: f64,
+    pub per_tick: f64,
+}
+
+impl RecoverCost for Linear {
+    fn cost(&self, idle_ticks: u64) -> f64 {
+        self.base + self.per_tick * idle_ticks as f64
+    }
+}
+
+/// Omniscient algorithm: if we are keeping, and if the cost of keeping until the next
+/// access is >= C, then discard. After the last access the object is never recovered
+/// again, so it is discarded for free.
 #[derive(Debug, Clone)]
 pub struct OfflineInstance<T>
 where
     T: Iterator<Item = u64>,
 {
-    t: u64,
-    access_list: Peekable<T>,
-    keep_cost: u64,
-    recover_cost: u64,
-    accrued_cost: f64,
-    policy: Policy,
+    inner: n_tier::OfflineInstance<T>,
 }
 
 impl<T> OfflineInstance<T>
 where
-    T: Iterator<Item = u64>,
+    T: Iterator<Item = u64> + Clone,
 {
-    pub fn new(keep_cost: u64, recover_cost: u64, access_list: Peekable<T>) -> OfflineInstance<T> {
+    /// # Panics
+    ///
+    /// Panics if either cost is negative.
+    pub fn new<C: Cost>(
+        keep_cost: C,
+        recover_cost: C,
+        access_list: Peekable<T>,
+    ) -> OfflineInstance<T> {
         Self {
-            t: 0,
-            access_list,
-            keep_cost,
-            recover_cost,
-            accrued_cost: 0.0,
-            policy: Policy::Keep,
+            inner: n_tier::OfflineInstance::new(tiers(keep_cost, recover_cost), access_list)
+                .expect("non-negative keep and discard costs are always ordered"),
+        }
+    }
+    /// Like [`OfflineInstance::new`], but starting under the given policy, e.g.
+    /// `Discard` for an object that begins in cold storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either cost is negative or the policy is `Compress`.
+    pub fn with_initial_policy<C: Cost>(
+        keep_cost: C,
+        recover_cost: C,
+        access_list: Peekable<T>,
+        policy: Policy,
+    ) -> OfflineInstance<T> {
+        Self {
+            inner: n_tier::OfflineInstance::with_initial_tier(
+                tiers(keep_cost, recover_cost),
+                access_list,
+                policy_tier(policy),
+            )
+            .expect("non-negative keep and discard costs are always ordered"),
         }
     }
 }
 
 impl<T> Algorithm for OfflineInstance<T>
 where
-    T: Iterator<Item = u64>,
+    T: Iterator<Item = u64> + Clone,
 {
     fn tick(&mut self, access: bool) {
-        self.t += 1;
-        // Omniscient algorithm: if we are keeping, and if the time to
-        // next access is > C, then discard
-        match (&self.policy, self.access_list.peek()) {
-            (Policy::Keep, Some(&elem)) => {
-                let time_to_next_access = elem - self.t;
-                if time_to_next_access >= self.recover_cost {
-                    self.policy = Policy::Discard;
-                }
-            }
-            _ => {}
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        1.0
+    }
+}
+
+/// 2-competitive algorithm. If the keep cost paid since last access is >= recover
+/// cost, then we should discard.
+#[derive(Debug, Clone)]
+pub struct NaiveInstance {
+    inner: n_tier::NaiveInstance,
+}
+
+impl NaiveInstance {
+    /// # Panics
+    ///
+    /// Panics if either cost is negative.
+    pub fn new<C: Cost>(keep_cost: C, recover_cost: C) -> Self {
+        Self {
+            inner: n_tier::NaiveInstance::new(tiers(keep_cost, recover_cost))
+                .expect("non-negative keep and discard costs are always ordered"),
+        }
+    }
+    /// Like [`NaiveInstance::new`], but starting under the given policy, e.g.
+    /// `Discard` for an object that begins in cold storage, so that the first access
+    /// pays to recover it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either cost is negative or the policy is `Compress`.
+    pub fn with_initial_policy<C: Cost>(keep_cost: C, recover_cost: C, policy: Policy) -> Self {
+        Self {
+            inner: n_tier::NaiveInstance::with_initial_tier(
+                tiers(keep_cost, recover_cost),
+                policy_tier(policy),
+            )
+            .expect("non-negative keep and discard costs are always ordered"),
+        }
+    }
+}
+
+impl Algorithm for NaiveInstance {
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        deterministic_lower_bound()
+    }
+}
+
+/// Baseline that never discards, paying the keep cost on every tick without an access.
+/// It bounds what holding the object can cost and shows how much the tiering decisions
+/// of the other algorithms save.
+#[derive(Debug, Clone)]
+pub struct AlwaysKeepInstance {
+    keep_cost: f64,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+}
+
+impl AlwaysKeepInstance {
+    pub fn new<C: Cost>(keep_cost: C) -> Self {
+        Self {
+            keep_cost: non_negative(keep_cost),
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+        }
+    }
+}
+
+impl Algorithm for AlwaysKeepInstance {
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        if !access {
+            self.accrued_cost += self.keep_cost;
+            self.breakdown.time_cost += self.keep_cost;
+        }
+    }
+    fn reset(&mut self) {
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+    fn current_policy(&self) -> Policy {
+        Policy::Keep
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
+}
+
+/// Baseline that discards on the first idle tick after every access and recovers on
+/// the next one, never paying to hold the object. It bounds what recovering can cost,
+/// the opposite extreme from [`AlwaysKeepInstance`].
+#[derive(Debug, Clone)]
+pub struct AlwaysDiscardInstance {
+    t: u64,
+    recover_cost: f64,
+    policy: Policy,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+}
+
+impl AlwaysDiscardInstance {
+    pub fn new<C: Cost>(recover_cost: C) -> Self {
+        Self {
+            t: 0,
+            recover_cost: non_negative(recover_cost),
+            policy: Policy::Keep,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
         }
+    }
+}
+
+impl Algorithm for AlwaysDiscardInstance {
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
         if !access {
             if matches!(self.policy, Policy::Keep) {
-                self.accrued_cost += self.keep_cost as f64;
+                log_transition!(self.t, self.policy, Policy::Discard, "discard: idle");
+                self.policy = Policy::Discard;
+                self.breakdown.num_discards += 1;
             }
             return;
         }
-        let _ = self.access_list.next();
-        // Incur a recovery cost if necessary.
         if matches!(self.policy, Policy::Discard) {
-            self.accrued_cost += self.recover_cost as f64;
+            self.accrued_cost += self.recover_cost;
+            self.breakdown.recovery_cost += self.recover_cost;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
             self.policy = Policy::Keep;
         }
     }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.policy = Policy::Keep;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
     fn total_accrued_cost(&self) -> f64 {
         self.accrued_cost
     }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
 }
 
+/// Deterministic algorithm that discards once the keep cost paid since the last access
+/// reaches the recover cost, like [`NaiveInstance`], but only after holding the object
+/// for at least `min_hold` ticks since it was last recovered. In bursty workloads this
+/// keeps the object through short lulls within a burst rather than thrashing between
+/// discarding and recovering it. With `min_hold` zero it is the naive algorithm.
 #[derive(Debug, Clone)]
-pub struct NaiveInstance {
+pub struct HysteresisInstance {
+    naive: NaiveInstance,
+    min_hold: u64,
+}
+
+impl HysteresisInstance {
+    /// # Panics
+    ///
+    /// Panics if either cost is negative.
+    pub fn new<C: Cost>(keep_cost: C, recover_cost: C, min_hold: u64) -> Self {
+        Self {
+            naive: NaiveInstance::new(keep_cost, recover_cost),
+            min_hold,
+        }
+    }
+}
+
+impl Algorithm for HysteresisInstance {
+    fn tick(&mut self, access: bool) {
+        let recoveries = self.naive.recovery_count();
+        self.naive.tick(access);
+        if self.naive.recovery_count() > recoveries {
+            self.naive.inner.hold_for(self.min_hold);
+        }
+    }
+    fn reset(&mut self) {
+        self.naive.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.naive.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.naive.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.naive.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.naive.cost_breakdown()
+    }
+}
+
+/// Deterministic algorithm that discards once the keep cost paid since the last
+/// access reaches a fixed threshold d. At the break-even point d = C it is the
+/// 2-competitive algorithm, and sweeping d shows how the worst case degrades on either
+/// side of it.
+#[derive(Debug, Clone)]
+pub struct BreakEvenInstance<C: RecoverCost = Constant> {
     t: u64,
     keep_cost: u64,
-    recover_cost: u64,
+    recover: C,
+    discard_threshold: u64,
     policy: Policy,
     accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
     last_access: u64,
+    discarded_at: u64,
 }
 
-impl NaiveInstance {
+impl BreakEvenInstance {
+    /// Creates an instance that discards at the break-even point, once the keep cost
+    /// paid reaches the recover cost.
     pub fn new(keep_cost: u64, recover_cost: u64) -> Self {
+        Self::with_threshold(keep_cost, recover_cost, recover_cost)
+    }
+    pub fn with_threshold(keep_cost: u64, recover_cost: u64, discard_threshold: u64) -> Self {
+        Self::with_recover_cost(keep_cost, Constant(recover_cost as f64), discard_threshold)
+    }
+}
+
+impl<C: RecoverCost> BreakEvenInstance<C> {
+    /// Creates an instance that discards once the keep cost paid reaches
+    /// `discard_threshold` and charges `recover` for each recovery.
+    pub fn with_recover_cost(keep_cost: u64, recover: C, discard_threshold: u64) -> Self {
         Self {
             t: 0,
             last_access: 0,
+            discarded_at: 0,
             keep_cost,
-            recover_cost,
+            recover,
+            discard_threshold,
             policy: Policy::Keep,
             accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
         }
     }
+    /// Starts the clock at tick `t`, as if the object had just been accessed then,
+    /// e.g. to resume a long-running simulation.
+    pub fn starting_at(mut self, t: u64) -> Self {
+        self.t = t;
+        self.last_access = t;
+        self
+    }
 }
 
-impl Algorithm for NaiveInstance {
+impl<C: RecoverCost> Algorithm for BreakEvenInstance<C> {
     fn tick(&mut self, access: bool) {
-        self.t += 1;
-        // 2-competitive algorithm. If time since last access
-        // is >= recover cost, then we should discard.
-        let should_discard = (self.t - self.last_access) >= self.recover_cost;
-        if matches!(self.policy, Policy::Keep) && should_discard {
-            self.policy = Policy::Discard;
-        }
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
         if !access {
+            // Only idle ticks discard, so an access finds the object as the previous
+            // tick left it.
+            let keep_cost_paid = self
+                .keep_cost
+                .saturating_mul(self.t.saturating_sub(self.last_access));
+            if matches!(self.policy, Policy::Keep) && keep_cost_paid >= self.discard_threshold {
+                log_transition!(
+                    self.t,
+                    self.policy,
+                    Policy::Discard,
+                    "discard: keep cost paid {} >= threshold {}",
+                    keep_cost_paid,
+                    self.discard_threshold
+                );
+                self.policy = Policy::Discard;
+                self.discarded_at = self.t;
+                self.breakdown.num_discards += 1;
+            }
             if matches!(self.policy, Policy::Keep) {
                 self.accrued_cost += self.keep_cost as f64;
+                self.breakdown.time_cost += self.keep_cost as f64;
             }
             return;
         }
@@ -107,134 +487,2260 @@ impl Algorithm for NaiveInstance {
 
         // Incur a recovery cost if necessary.
         if matches!(self.policy, Policy::Discard) {
-            self.accrued_cost += self.recover_cost as f64;
+            let recovery_cost = self.recover.cost(self.t.saturating_sub(self.discarded_at));
+            self.accrued_cost += recovery_cost;
+            self.breakdown.recovery_cost += recovery_cost;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
             self.policy = Policy::Keep;
         }
     }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.last_access = 0;
+        self.discarded_at = 0;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+        self.policy = Policy::Keep;
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
     fn total_accrued_cost(&self) -> f64 {
         self.accrued_cost
     }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
 }
 
+/// Omniscient algorithm under a keep cost that varies by tick, e.g. time-of-day
+/// pricing, given by a schedule from the tick to its keep cost. Since recovering costs
+/// the same whenever the object was discarded, it either keeps the object through an
+/// idle gap or discards it right after the access, whichever is cheaper. After the
+/// last access it discards for free.
 #[derive(Debug, Clone)]
-pub struct KarlinInstance {
+pub struct ScheduledOfflineInstance<T, F>
+where
+    T: Iterator<Item = u64>,
+    F: Fn(u64) -> u64,
+{
     t: u64,
-    keep_cost: u64,
+    keep_cost: F,
     recover_cost: u64,
     policy: Policy,
+    access_list: Peekable<T>,
+    initial_access_list: Peekable<T>,
     accrued_cost: f64,
-    last_access: u64,
-    t_to_wait_before_discard: u64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
 }
 
-impl KarlinInstance {
-    pub fn new(keep_cost: u64, recover_cost: u64) -> Self {
+impl<T, F> ScheduledOfflineInstance<T, F>
+where
+    T: Iterator<Item = u64> + Clone,
+    F: Fn(u64) -> u64,
+{
+    pub fn new(keep_cost: F, recover_cost: u64, access_list: Peekable<T>) -> Self {
         Self {
             t: 0,
-            last_access: 0,
             keep_cost,
             recover_cost,
             policy: Policy::Keep,
+            initial_access_list: access_list.clone(),
+            access_list,
             accrued_cost: 0.0,
-            t_to_wait_before_discard: karlin::sample(recover_cost),
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
         }
     }
 }
 
-impl Algorithm for KarlinInstance {
+impl<T, F> Algorithm for ScheduledOfflineInstance<T, F>
+where
+    T: Iterator<Item = u64> + Clone,
+    F: Fn(u64) -> u64,
+{
     fn tick(&mut self, access: bool) {
-        self.t += 1;
-        // Randomized competitive algorithm. If time since last access
-        // is >= D, we discard where 0 <= D <= C. We sample this D from a Karlin distribution
-        // after each access occurs.
-        let time_elapsed = self.t - self.last_access;
-        let should_discard = time_elapsed >= self.t_to_wait_before_discard;
-        if matches!(self.policy, Policy::Keep) && should_discard {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        while self.access_list.next_if(|&elem| elem < self.t).is_some() {}
+        if let (Policy::Keep, None, false) = (self.policy, self.access_list.peek(), access) {
+            log_transition!(
+                self.t,
+                self.policy,
+                Policy::Discard,
+                "discard: no access left"
+            );
             self.policy = Policy::Discard;
+            self.breakdown.num_discards += 1;
+        }
+        if let (Policy::Keep, Some(&elem)) = (self.policy, self.access_list.peek()) {
+            let gap_cost: u64 = (self.t..elem).map(&self.keep_cost).sum();
+            if gap_cost >= self.recover_cost {
+                log_transition!(
+                    self.t,
+                    self.policy,
+                    Policy::Discard,
+                    "discard: keep cost {} until the next access >= recover cost {}",
+                    gap_cost,
+                    self.recover_cost
+                );
+                self.policy = Policy::Discard;
+                self.breakdown.num_discards += 1;
+            }
         }
         if !access {
             if matches!(self.policy, Policy::Keep) {
-                self.accrued_cost += self.keep_cost as f64;
+                let keep_cost = (self.keep_cost)(self.t) as f64;
+                self.accrued_cost += keep_cost;
+                self.breakdown.time_cost += keep_cost;
             }
             return;
         }
-        self.t_to_wait_before_discard = karlin::sample(self.recover_cost);
-        self.last_access = self.t;
-
-        // Incur a recovery cost if necessary.
+        let _ = self.access_list.next();
         if matches!(self.policy, Policy::Discard) {
             self.accrued_cost += self.recover_cost as f64;
+            self.breakdown.recovery_cost += self.recover_cost as f64;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
             self.policy = Policy::Keep;
         }
     }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.policy = Policy::Keep;
+        self.access_list = self.initial_access_list.clone();
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
     fn total_accrued_cost(&self) -> f64 {
         self.accrued_cost
     }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
 }
 
-pub fn calculate_competitive_ratio<T: Algorithm>(
-    instance: T,
-    keep_cost: u64,
+/// 2-competitive algorithm under a keep cost schedule. It discards once the keep cost
+/// paid since the last access, including the current tick, reaches the recover cost.
+#[derive(Debug, Clone)]
+pub struct ScheduledNaiveInstance<F: Fn(u64) -> u64> {
+    t: u64,
+    keep_cost: F,
     recover_cost: u64,
-    access_list: Vec<u64>,
-    num_ticks: u64,
-) -> f64 {
-    // Offline, omniscient instance.
-    let offline = OfflineInstance::new(
-        keep_cost,
-        recover_cost,
-        access_list.clone().into_iter().peekable(),
-    );
-    let mut sim = crate::Simulator::new(access_list.clone(), offline);
-    for _ in 0..num_ticks {
-        sim.tick();
+    keep_cost_paid: u64,
+    policy: Policy,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+}
+
+impl<F: Fn(u64) -> u64> ScheduledNaiveInstance<F> {
+    pub fn new(keep_cost: F, recover_cost: u64) -> Self {
+        Self {
+            t: 0,
+            keep_cost,
+            recover_cost,
+            keep_cost_paid: 0,
+            policy: Policy::Keep,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+        }
     }
-    let offline_cost = sim.node.total_accrued_cost();
+}
 
-    // Online instance.
-    let mut sim = crate::Simulator::new(access_list, instance);
-    for _ in 0..num_ticks {
-        sim.tick();
+impl<F: Fn(u64) -> u64> Algorithm for ScheduledNaiveInstance<F> {
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        let keep_cost = (self.keep_cost)(self.t);
+        if !access {
+            if matches!(self.policy, Policy::Keep)
+                && self.keep_cost_paid + keep_cost >= self.recover_cost
+            {
+                log_transition!(
+                    self.t,
+                    self.policy,
+                    Policy::Discard,
+                    "discard: keep cost paid {} >= recover cost {}",
+                    self.keep_cost_paid + keep_cost,
+                    self.recover_cost
+                );
+                self.policy = Policy::Discard;
+                self.breakdown.num_discards += 1;
+            }
+            if matches!(self.policy, Policy::Keep) {
+                self.keep_cost_paid += keep_cost;
+                self.accrued_cost += keep_cost as f64;
+                self.breakdown.time_cost += keep_cost as f64;
+            }
+            return;
+        }
+        self.keep_cost_paid = 0;
+        if matches!(self.policy, Policy::Discard) {
+            self.accrued_cost += self.recover_cost as f64;
+            self.breakdown.recovery_cost += self.recover_cost as f64;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
+            self.policy = Policy::Keep;
+        }
+    }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.keep_cost_paid = 0;
+        self.policy = Policy::Keep;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
     }
-    let online_cost = sim.node.total_accrued_cost();
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
+}
 
-    // Competitive ratio.
-    online_cost / offline_cost
+/// Randomized algorithm that discards once the keep cost paid since the last access
+/// reaches a threshold sampled after every access, by default from the Karlin density;
+/// see [`n_tier::KarlinInstance`].
+#[derive(Debug, Clone)]
+pub struct KarlinInstance<
+    R: Rng = ThreadRng,
+    C: RecoverCost = Constant,
+    D: WaitDistribution = KarlinDistribution,
+> {
+    inner: n_tier::KarlinInstance<R, DiscardThresholds<D>>,
+    keep_cost: f64,
+    /// Cost of recovering right after a discard, `recover.cost(0)`, which the discard
+    /// thresholds are sampled for.
+    recover_cost: f64,
+    recover: C,
+    /// Ticks the object has stayed discarded since it was last kept.
+    discarded_ticks: u64,
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    #[test]
-    fn two_competitive() {
-        let keep_cost = 1u64;
-        let recover_cost = 3u64;
-        let num_ticks = 11;
-        let access_list = vec![4, 8, 12];
-        let online_instance = NaiveInstance::new(keep_cost, recover_cost);
-        let competitive_ratio = calculate_competitive_ratio(
-            online_instance,
-            keep_cost,
-            recover_cost,
-            access_list,
-            num_ticks,
-        );
-        assert_eq!(2.0, competitive_ratio);
+/// Samples the discard threshold of a [`KarlinInstance`] from its wait distribution, or
+/// from the continuous Karlin density, and records every sample.
+#[derive(Debug, Clone)]
+struct DiscardThresholds<D> {
+    distribution: D,
+    continuous: bool,
+    sampled: Vec<f64>,
+}
+
+impl<D: WaitDistribution> n_tier::ThresholdSampler for DiscardThresholds<D> {
+    fn sample_thresholds<R: Rng>(&mut self, tiers: &[Tier], rng: &mut R) -> Vec<f64> {
+        let recover_cost = tiers[1].recover_cost;
+        let threshold = if self.continuous {
+            karlin::sample_continuous_with_rng(recover_cost, rng)
+        } else {
+            self.distribution.sample(recover_cost as u64, rng) as f64
+        };
+        self.sampled.push(threshold);
+        vec![0.0, threshold]
     }
-    #[test]
-    fn randomized_competitive() {
-        let keep_cost = 1u64;
-        let recover_cost = 3u64;
-        let num_ticks = 11;
-        let access_list = vec![4, 8, 12];
-        let online_instance = KarlinInstance::new(keep_cost, recover_cost);
-        let competitive_ratio = calculate_competitive_ratio(
-            online_instance,
-            keep_cost,
-            recover_cost,
+}
+
+impl KarlinInstance {
+    /// Creates an instance that samples integer discard thresholds from the discrete
+    /// karlin density, or fractional ones from the continuous density if the recover
+    /// cost is fractional, since the discrete density is over the integers in [0, C].
+    pub fn new<K: Cost>(keep_cost: K, recover_cost: K) -> Self {
+        Self::with_rng(keep_cost, recover_cost, thread_rng())
+    }
+    /// Creates an instance that samples fractional discard thresholds from the
+    /// continuous karlin density rather than its integer discretization.
+    pub fn continuous<K: Cost>(keep_cost: K, recover_cost: K) -> Self {
+        Self::continuous_with_rng(keep_cost, recover_cost, thread_rng())
+    }
+    /// Exact expected cost of an instance with integer thresholds run until the last
+    /// access in the sorted access list, summing over the distribution of the
+    /// threshold D sampled for each gap rather than simulating. On a gap of n idle
+    /// ticks before an access, the instance discards on the first idle tick j with
+    /// keep_cost * (j - 1) >= D, so it keeps through min(ceil(D / keep_cost), n) idle
+    /// ticks and pays the recover cost if it discarded by the last of them.
+    pub fn expected_cost(access_list: &[u64], keep_cost: u64, recover_cost: u64) -> f64 {
+        let gap_cost = |idle: u64| -> f64 {
+            (0..=recover_cost)
+                .map(|d| {
+                    let (kept, discarded) = if keep_cost == 0 {
+                        (0, d == 0 && idle > 0)
+                    } else {
+                        let kept = d.div_ceil(keep_cost);
+                        (kept.min(idle), kept < idle)
+                    };
+                    let recover = if discarded { recover_cost } else { 0 };
+                    karlin::normalized_pdf(d, recover_cost) * (keep_cost * kept + recover) as f64
+                })
+                .sum()
+        };
+        let mut last_access = 0;
+        let mut cost = 0.0;
+        for &t in access_list {
+            if t > last_access {
+                cost += gap_cost(t - last_access - 1);
+                last_access = t;
+            }
+        }
+        cost
+    }
+}
+
+impl<R: Rng> KarlinInstance<R> {
+    /// Creates an instance that samples its discard thresholds from the given rng,
+    /// which makes runs reproducible when the rng is seeded.
+    pub fn with_rng<K: Cost>(keep_cost: K, recover_cost: K, rng: R) -> Self {
+        Self::with_recover_cost(keep_cost, Constant(non_negative(recover_cost)), rng)
+    }
+    /// Like [`KarlinInstance::continuous`], sampling from the given rng.
+    pub fn continuous_with_rng<K: Cost>(keep_cost: K, recover_cost: K, rng: R) -> Self {
+        Self::build(
+            non_negative(keep_cost),
+            Constant(non_negative(recover_cost)),
+            KarlinDistribution::default(),
+            true,
+            rng,
+        )
+    }
+}
+
+impl<R: Rng, C: RecoverCost> KarlinInstance<R, C> {
+    /// Creates an instance that charges `recover` for each recovery. Its discard
+    /// thresholds are sampled for the cost of recovering right after a discard,
+    /// `recover.cost(0)`, so later recoveries that cost more are not anticipated.
+    pub fn with_recover_cost<K: Cost>(keep_cost: K, recover: C, rng: R) -> Self {
+        Self::build(
+            non_negative(keep_cost),
+            recover,
+            KarlinDistribution::default(),
+            false,
+            rng,
+        )
+    }
+}
+
+impl<R: Rng, D: WaitDistribution> KarlinInstance<R, Constant, D> {
+    /// Creates an instance that samples its discard thresholds from `distribution`
+    /// instead of the Karlin density, to compare alternative waits against it.
+    pub fn with_distribution<K: Cost>(
+        keep_cost: K,
+        recover_cost: K,
+        distribution: D,
+        rng: R,
+    ) -> Self {
+        Self::build(
+            non_negative(keep_cost),
+            Constant(non_negative(recover_cost)),
+            distribution,
+            false,
+            rng,
+        )
+    }
+}
+
+impl<R: Rng, C: RecoverCost, D: WaitDistribution> KarlinInstance<R, C, D> {
+    /// # Panics
+    ///
+    /// Panics if recovering right after a discard has a negative cost.
+    fn build(keep_cost: f64, recover: C, distribution: D, continuous: bool, rng: R) -> Self {
+        let recover_cost = recover.cost(0);
+        assert!(
+            recover_cost >= 0.0,
+            "costs must be non-negative, got {}",
+            recover_cost
+        );
+        let sampler = DiscardThresholds {
+            distribution,
+            continuous: continuous || recover_cost.fract() != 0.0,
+            sampled: vec![],
+        };
+        Self {
+            inner: n_tier::KarlinInstance::with_sampler(
+                tiers(keep_cost, recover_cost),
+                sampler,
+                rng,
+            )
+            .expect("non-negative keep and discard costs are always ordered"),
+            keep_cost,
+            recover_cost,
+            recover,
+            discarded_ticks: 0,
+        }
+    }
+    /// Every discard threshold sampled so far, starting with the one drawn at
+    /// construction and followed by one per access.
+    pub fn sampled_discard_thresholds(&self) -> &[f64] {
+        &self.inner.sampler().sampled
+    }
+    /// Keep cost paid since the last access at which the object is discarded, as
+    /// sampled after that access. Continuous instances sample fractional thresholds,
+    /// which are rounded up here; with a whole keep cost the object is discarded on
+    /// the same tick either way. See [`KarlinInstance::current_continuous_discard_threshold`]
+    /// for the exact value.
+    pub fn current_discard_threshold(&self) -> u64 {
+        self.current_continuous_discard_threshold().ceil() as u64
+    }
+    /// Like [`KarlinInstance::current_discard_threshold`], without rounding the
+    /// thresholds of continuous instances.
+    pub fn current_continuous_discard_threshold(&self) -> f64 {
+        self.inner.thresholds()[1]
+    }
+    /// Starts the clock at tick `t`, as if the object had just been accessed then,
+    /// e.g. to resume a long-running simulation.
+    pub fn starting_at(mut self, t: u64) -> Self {
+        self.inner.start_at(t);
+        self
+    }
+}
+
+impl<R: Rng, C: RecoverCost, D: WaitDistribution> Algorithm for KarlinInstance<R, C, D> {
+    fn tick(&mut self, access: bool) {
+        // Randomized competitive algorithm. If the keep cost paid since last access
+        // is >= D, we discard where 0 <= D <= C. We sample this D from a Karlin distribution
+        // after each access occurs. Only idle ticks discard, and they compare the keep
+        // cost paid before them, so D = 0 discards on the first idle tick and D = 1 on
+        // the second.
+        let recoveries = self.inner.recovery_count();
+        self.inner.tick(access);
+        if self.inner.recovery_count() > recoveries {
+            // The tiers charge the cost of recovering right after a discard, so charge
+            // whatever staying discarded added to it.
+            let extra = self.recover.cost(self.discarded_ticks) - self.recover_cost;
+            self.inner.charge_extra_recovery(extra);
+        }
+        if matches!(self.inner.current_policy(), Policy::Discard) {
+            self.discarded_ticks += 1;
+        } else {
+            self.discarded_ticks = 0;
+        }
+    }
+    fn reset(&mut self) {
+        self.inner.sampler_mut().sampled.clear();
+        self.inner.reset();
+        self.discarded_ticks = 0;
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    /// Exact worst-case expected ratio in discrete time for waits drawn from the
+    /// instance's integer distribution, assuming each recovery is charged the recover
+    /// cost the waits are sampled for; see [`worst_gap_ratio`]. For the Karlin density
+    /// and a unit keep cost it stays below the deterministic bound of 2 and falls
+    /// toward e / (e - 1) as C grows. Keep costs close to or above C round the
+    /// thresholds to few idle ticks, which can push it above 2. Continuous thresholds
+    /// are not covered.
+    fn competitive_bound(&self) -> f64 {
+        let sampler = self.inner.sampler();
+        if sampler.continuous {
+            return f64::INFINITY;
+        }
+        let recover_cost = self.recover_cost as u64;
+        let waits: Vec<(f64, f64)> = (0..=recover_cost)
+            .map(|wait| (wait as f64, sampler.distribution.pdf(wait, recover_cost)))
+            .collect();
+        worst_gap_ratio(self.keep_cost, self.recover_cost, &waits)
+    }
+}
+
+/// Randomized primal-dual algorithm. The fractional amount of data discarded grows
+/// geometrically with idle time, reaching one once the keep cost paid matches the
+/// recovery cost. Rounding against a uniform threshold drawn after each access
+/// discards as soon as the fractional amount exceeds it.
+#[derive(Debug, Clone)]
+pub struct PrimalDualInstance<R: Rng = ThreadRng> {
+    t: u64,
+    keep_cost: u64,
+    recover_cost: u64,
+    policy: Policy,
+    accrued_cost: f64,
+    accrued_cost_before_tick: f64,
+    breakdown: CostBreakdown,
+    last_access: u64,
+    discard_threshold: f64,
+    rng: R,
+}
+
+impl PrimalDualInstance {
+    pub fn new(keep_cost: u64, recover_cost: u64) -> Self {
+        Self::with_rng(keep_cost, recover_cost, thread_rng())
+    }
+}
+
+impl<R: Rng> PrimalDualInstance<R> {
+    pub fn with_rng(keep_cost: u64, recover_cost: u64, mut rng: R) -> Self {
+        Self {
+            t: 0,
+            last_access: 0,
+            keep_cost,
+            recover_cost,
+            policy: Policy::Keep,
+            accrued_cost: 0.0,
+            accrued_cost_before_tick: 0.0,
+            breakdown: CostBreakdown::default(),
+            discard_threshold: rng.gen(),
+            rng,
+        }
+    }
+    /// Fractional amount discarded after the given number of idle ticks.
+    fn discarded_fraction(&self, time_elapsed: u64) -> f64 {
+        let b = self.recover_cost as f64 / self.keep_cost as f64;
+        let growth = 1.0 + 1.0 / b;
+        (growth.powf(time_elapsed as f64) - 1.0) / (growth.powf(b) - 1.0)
+    }
+}
+
+impl<R: Rng> Algorithm for PrimalDualInstance<R> {
+    fn tick(&mut self, access: bool) {
+        self.accrued_cost_before_tick = self.accrued_cost;
+        self.t = crate::next_tick(self.t);
+        // Only idle ticks discard, rounding the fraction reached by the idle ticks
+        // before this one.
+        if !access {
+            let time_elapsed = self.t.saturating_sub(self.last_access + 1);
+            let should_discard = self.discarded_fraction(time_elapsed) >= self.discard_threshold;
+            if matches!(self.policy, Policy::Keep) && should_discard {
+                log_transition!(
+                    self.t,
+                    self.policy,
+                    Policy::Discard,
+                    "discard: discarded fraction {:.3} >= threshold {:.3}",
+                    self.discarded_fraction(time_elapsed),
+                    self.discard_threshold
+                );
+                self.policy = Policy::Discard;
+                self.breakdown.num_discards += 1;
+            }
+            if matches!(self.policy, Policy::Keep) {
+                self.accrued_cost += self.keep_cost as f64;
+                self.breakdown.time_cost += self.keep_cost as f64;
+            }
+            return;
+        }
+        self.discard_threshold = self.rng.gen();
+        self.last_access = self.t;
+
+        // Incur a recovery cost if necessary.
+        if matches!(self.policy, Policy::Discard) {
+            self.accrued_cost += self.recover_cost as f64;
+            self.breakdown.recovery_cost += self.recover_cost as f64;
+            self.breakdown.num_recoveries += 1;
+            log_transition!(self.t, self.policy, Policy::Keep, "recover: accessed");
+            self.policy = Policy::Keep;
+        }
+    }
+    fn reset(&mut self) {
+        self.t = 0;
+        self.last_access = 0;
+        self.accrued_cost = 0.0;
+        self.accrued_cost_before_tick = 0.0;
+        self.breakdown = CostBreakdown::default();
+        self.policy = Policy::Keep;
+        self.discard_threshold = self.rng.gen();
+    }
+    fn current_policy(&self) -> Policy {
+        self.policy
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.accrued_cost
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.accrued_cost - self.accrued_cost_before_tick
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.breakdown
+    }
+}
+
+/// Runs an online instance and the offline optimum over the access list for
+/// `num_ticks` ticks and returns the ratio of their costs. To compare several online
+/// instances on the same list, compute [`offline_cost`] once and pass it to
+/// [`crate::online_ratio`] for each instead.
+///
+/// # Panics
+///
+/// Panics if the access list is not sorted and distinct, or accesses tick 0; see
+/// [`validate_access_list`]. Accesses past `num_ticks` are allowed and never reached.
+pub fn calculate_competitive_ratio<T: Algorithm, C: Cost, L: AsRef<[u64]>>(
+    instance: T,
+    keep_cost: C,
+    recover_cost: C,
+    access_list: L,
+    num_ticks: u64,
+) -> f64 {
+    let access_list = access_list.as_ref();
+    let offline_cost = offline_cost(keep_cost, recover_cost, access_list, num_ticks);
+    crate::online_ratio(instance, access_list, num_ticks, offline_cost)
+}
+
+/// Cost of the offline optimum over the access list for `num_ticks` ticks. Accesses
+/// after `num_ticks` are ignored; see [`trim_access_list`].
+///
+/// # Panics
+///
+/// Panics if the access list is not sorted and distinct, or accesses tick 0; see
+/// [`validate_access_list`].
+pub fn offline_cost<C: Cost>(
+    keep_cost: C,
+    recover_cost: C,
+    access_list: &[u64],
+    num_ticks: u64,
+) -> f64 {
+    if let Err(err) = validate_access_list(access_list, u64::MAX) {
+        panic!("invalid access list: {}", err);
+    }
+    let access_list = trim_access_list(access_list, num_ticks);
+    let offline = OfflineInstance::new(
+        keep_cost,
+        recover_cost,
+        access_list.iter().copied().peekable(),
+    );
+    let mut sim = crate::Simulator::new(access_list.iter().copied(), offline);
+    sim.run(num_ticks);
+    sim.node.total_accrued_cost()
+}
+
+/// Randomized learning-augmented algorithm of Kumar, Purohit and Svitkina, given a
+/// prediction of the number of ticks from each access until the next one and a
+/// trade-off parameter `lambda` in (0, 1]. Let b = C / keep_cost be the number of
+/// ticks it takes the keep cost to reach the recover cost. After every access it draws
+/// how many idle ticks to keep the object through, i in [0, n), with probability
+/// proportional to (1 - 1 / b)^(n - 1 - i): n = floor(lambda * b) if the predicted gap
+/// makes discarding worthwhile and n = ceil(b / lambda) otherwise. In continuous time
+/// this is lambda / (1 - e^-lambda)-consistent and 1 / (1 - e^-(lambda - 1 / b))-robust
+/// when the prediction is long, and (lambda + 1 / b) / (1 - e^-lambda)-consistent and
+/// 1 / (1 - e^-lambda)-robust when it is short; [`LearnedInstance::consistency`] and
+/// [`LearnedInstance::robustness`] give the exact discrete ratios. Lambda = 1 ignores
+/// the prediction and stays within e / (e - 1), and as lambda shrinks the
+/// algorithm commits to the prediction, approaching the offline cost when it is right
+/// at the price of a weaker guarantee when it is wrong.
+#[derive(Debug, Clone)]
+pub struct LearnedInstance<R: Rng = ThreadRng> {
+    inner: n_tier::KarlinInstance<R, ThresholdDistribution>,
+    keep_cost: f64,
+    recover_cost: f64,
+    predicted_next_access: u64,
+}
+
+/// Discard thresholds drawn after every access from a fixed distribution, given as
+/// `(threshold, probability)` pairs of keep cost paid since the access. A single
+/// threshold is deterministic and draws nothing from the rng.
+#[derive(Debug, Clone)]
+struct ThresholdDistribution {
+    waits: Vec<(f64, f64)>,
+}
+
+impl n_tier::ThresholdSampler for ThresholdDistribution {
+    fn sample_thresholds<R: Rng>(&mut self, _tiers: &[Tier], rng: &mut R) -> Vec<f64> {
+        if let [(threshold, _)] = self.waits[..] {
+            return vec![0.0, threshold];
+        }
+        let mut u: f64 = rng.gen();
+        let threshold = self
+            .waits
+            .iter()
+            .find(|&&(_, probability)| {
+                u -= probability;
+                u < 0.0
+            })
+            .or(self.waits.last())
+            .map_or(0.0, |&(threshold, _)| threshold);
+        vec![0.0, threshold]
+    }
+}
+
+impl LearnedInstance {
+    pub fn new(keep_cost: u64, recover_cost: u64, predicted_next_access: u64, lambda: f64) -> Self {
+        Self::with_rng(
+            keep_cost,
+            recover_cost,
+            predicted_next_access,
+            lambda,
+            thread_rng(),
+        )
+    }
+}
+
+impl<R: Rng> LearnedInstance<R> {
+    /// Creates an instance that draws its discard thresholds from the given rng.
+    ///
+    /// # Panics
+    ///
+    /// Panics if lambda is outside (0, 1].
+    pub fn with_rng(
+        keep_cost: u64,
+        recover_cost: u64,
+        predicted_next_access: u64,
+        lambda: f64,
+        rng: R,
+    ) -> Self {
+        assert!(lambda > 0.0 && lambda <= 1.0, "lambda must be in (0, 1]");
+        let waits = if keep_cost == 0 {
+            vec![(f64::INFINITY, 1.0)]
+        } else {
+            let b = recover_cost as f64 / keep_cost as f64;
+            let num_waits = if keep_cost.saturating_mul(predicted_next_access) >= recover_cost {
+                (lambda * b).floor().max(1.0)
+            } else {
+                (b / lambda).ceil().max(1.0)
+            } as u64;
+            let base = (1.0 - 1.0 / b).max(0.0);
+            let weights: Vec<f64> = (0..num_waits)
+                .map(|i| base.powi((num_waits - 1 - i) as i32))
+                .collect();
+            let total: f64 = weights.iter().sum();
+            weights
+                .into_iter()
+                .enumerate()
+                .map(|(i, weight)| ((i as u64 * keep_cost) as f64, weight / total))
+                .collect()
+        };
+        Self {
+            inner: n_tier::KarlinInstance::with_sampler(
+                tiers(keep_cost, recover_cost),
+                ThresholdDistribution { waits },
+                rng,
+            )
+            .expect("non-negative keep and discard costs are always ordered"),
+            keep_cost: keep_cost as f64,
+            recover_cost: recover_cost as f64,
+            predicted_next_access,
+        }
+    }
+    /// Exact expected competitive ratio when the prediction is correct, i.e. on
+    /// accesses spaced `predicted_next_access` ticks apart.
+    pub fn consistency(&self) -> f64 {
+        let idle = self.predicted_next_access.saturating_sub(1);
+        gap_ratio(
+            self.keep_cost,
+            self.recover_cost,
+            &self.inner.sampler().waits,
+            idle,
+        )
+    }
+    /// Exact worst-case expected competitive ratio however wrong the prediction is. At
+    /// lambda = 1 it is just below [`theoretical_competitive_ratio`], the discrete-time
+    /// optimum, and it grows as lambda shrinks.
+    pub fn robustness(&self) -> f64 {
+        worst_gap_ratio(
+            self.keep_cost,
+            self.recover_cost,
+            &self.inner.sampler().waits,
+        )
+    }
+}
+
+impl<R: Rng> Algorithm for LearnedInstance<R> {
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+    fn competitive_bound(&self) -> f64 {
+        self.robustness()
+    }
+}
+
+/// Deterministic learning-augmented algorithm given a prediction of the number of
+/// ticks from each access until the next one, and a confidence in [0, 1] in that
+/// prediction. If the predicted gap makes discarding worthwhile it discards once the
+/// keep cost paid reaches (1 - confidence) * C, and otherwise holds out until
+/// C / (1 - confidence). A confidence of 0 ignores the prediction and recovers the
+/// 2-competitive algorithm, while a confidence of 1 follows the prediction blindly.
+#[derive(Debug, Clone)]
+pub struct PredictiveInstance {
+    inner: n_tier::KarlinInstance<ThreadRng, ThresholdDistribution>,
+    confidence: f64,
+}
+
+impl PredictiveInstance {
+    pub fn new(
+        keep_cost: u64,
+        recover_cost: u64,
+        predicted_next_access: u64,
+        confidence: f64,
+    ) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&confidence),
+            "confidence must be in [0, 1]"
+        );
+        let distrust = 1.0 - confidence;
+        let discard_threshold = if keep_cost.saturating_mul(predicted_next_access) >= recover_cost {
+            distrust * recover_cost as f64
+        } else {
+            recover_cost as f64 / distrust
+        };
+        // The threshold counts the keep cost of the tick that discards, while the
+        // sampled thresholds only count what was paid before it.
+        let waits = vec![(discard_threshold - keep_cost as f64, 1.0)];
+        Self {
+            inner: n_tier::KarlinInstance::with_sampler(
+                tiers(keep_cost, recover_cost),
+                ThresholdDistribution { waits },
+                thread_rng(),
+            )
+            .expect("non-negative keep and discard costs are always ordered"),
+            confidence,
+        }
+    }
+    /// Competitive ratio when the prediction is correct, 2 - confidence.
+    pub fn consistency(&self) -> f64 {
+        2.0 - self.confidence
+    }
+    /// Competitive ratio however wrong the prediction is, 1 + 1 / (1 - confidence),
+    /// which is unbounded when the prediction is fully trusted.
+    pub fn robustness(&self) -> f64 {
+        1.0 + 1.0 / (1.0 - self.confidence)
+    }
+}
+
+impl Algorithm for PredictiveInstance {
+    fn tick(&mut self, access: bool) {
+        self.inner.tick(access);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn current_policy(&self) -> Policy {
+        self.inner.current_policy()
+    }
+    fn total_accrued_cost(&self) -> f64 {
+        self.inner.total_accrued_cost()
+    }
+    fn last_tick_cost(&self) -> f64 {
+        self.inner.last_tick_cost()
+    }
+    fn cost_breakdown(&self) -> CostBreakdown {
+        self.inner.cost_breakdown()
+    }
+}
+
+/// A two-tier algorithm chosen at runtime, e.g. by name from the command line, along
+/// with whatever it needs beyond the costs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Strategy {
+    /// The 2-competitive [`NaiveInstance`].
+    Naive,
+    /// The randomized [`KarlinInstance`], sampling from the thread-local rng.
+    KarlinRandomized,
+    /// The omniscient [`OfflineInstance`], which needs the whole access list up front.
+    Offline { access_list: Vec<u64> },
+    /// The learning-augmented [`PredictiveInstance`].
+    Predictive {
+        predicted_next_access: u64,
+        confidence: f64,
+    },
+}
+
+impl Strategy {
+    /// Creates a fresh instance of the strategy for the given costs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a predictive strategy's confidence is outside [0, 1].
+    pub fn build(&self, keep_cost: u64, recover_cost: u64) -> Box<dyn Algorithm> {
+        match self {
+            Strategy::Naive => Box::new(NaiveInstance::new(keep_cost, recover_cost)),
+            Strategy::KarlinRandomized => Box::new(KarlinInstance::new(keep_cost, recover_cost)),
+            Strategy::Offline { access_list } => Box::new(OfflineInstance::new(
+                keep_cost,
+                recover_cost,
+                access_list.clone().into_iter().peekable(),
+            )),
+            Strategy::Predictive {
+                predicted_next_access,
+                confidence,
+            } => Box::new(PredictiveInstance::new(
+                keep_cost,
+                recover_cost,
+                *predicted_next_access,
+                *confidence,
+            )),
+        }
+    }
+}
+
+/// Exact worst-case expected competitive ratio of `KarlinInstance` with a unit keep
+/// cost, accounting for its integer thresholds. In continuous time the worst case is
+/// e / (e - 1), but the sampled threshold D only takes integer values in [0, C]. The
+/// instance discards on the first idle tick after paying D, so for an idle gap of n
+/// ticks before an access it keeps for min(D, n) ticks and pays C whenever D < n,
+/// while the optimum pays min(n, C); see [`worst_gap_ratio`].
+pub fn analytic_worst_case_ratio(recover_cost: u64) -> f64 {
+    let waits: Vec<(f64, f64)> = (0..=recover_cost)
+        .map(|d| (d as f64, karlin::normalized_pdf(d, recover_cost)))
+        .collect();
+    worst_gap_ratio(1.0, recover_cost as f64, &waits)
+}
+
+/// Exact worst-case expected competitive ratio of an online two-tier algorithm that
+/// discards on the first idle tick whose keep cost paid before it reaches a threshold,
+/// drawn after every access from `waits`, given as `(threshold, probability)` pairs.
+/// On a gap of n idle ticks before an access, a threshold D keeps through
+/// min(ceil(D / keep_cost), n) idle ticks and pays the recover cost if that is fewer
+/// than n, while the optimum pays min(keep_cost * n, C). Thresholds are resampled after
+/// every access, so the worst ratio over an access list is the worst over a single
+/// gap, and gaps longer than both C / keep_cost and every threshold cost both the
+/// same as the first one that is. An
+/// access with no idle tick before it costs both nothing.
+fn worst_gap_ratio(keep_cost: f64, recover_cost: f64, waits: &[(f64, f64)]) -> f64 {
+    let longest_gap = if keep_cost > 0.0 {
+        let longest_threshold = waits
+            .iter()
+            .map(|&(threshold, _)| threshold)
+            .fold(recover_cost, f64::max);
+        (longest_threshold / keep_cost).ceil() as u64 + 1
+    } else {
+        1
+    };
+    (1..=longest_gap)
+        .map(|idle| gap_ratio(keep_cost, recover_cost, waits, idle))
+        .fold(1.0, f64::max)
+}
+
+/// Exact expected competitive ratio over a single gap of `idle` idle ticks before an
+/// access; see [`worst_gap_ratio`].
+fn gap_ratio(keep_cost: f64, recover_cost: f64, waits: &[(f64, f64)], idle: u64) -> f64 {
+    let online: f64 = waits
+        .iter()
+        .map(|&(threshold, probability)| {
+            let (kept, discarded) = if keep_cost > 0.0 {
+                let kept = (threshold / keep_cost).ceil().max(0.0);
+                (kept.min(idle as f64), kept < idle as f64)
+            } else {
+                (0.0, threshold <= 0.0)
+            };
+            let recover = if discarded { recover_cost } else { 0.0 };
+            probability * (keep_cost * kept + recover)
+        })
+        .sum();
+    let offline = (keep_cost * idle as f64).min(recover_cost);
+    if offline > 0.0 {
+        online / offline
+    } else if online > 0.0 {
+        f64::INFINITY
+    } else {
+        1.0
+    }
+}
+
+/// Worst-case expected competitive ratio of the randomized algorithm in continuous
+/// time, e / (e - 1), which is the best any randomized online algorithm can guarantee.
+/// The integer thresholds of `KarlinInstance` make its exact worst case, given by
+/// [`analytic_worst_case_ratio`], slightly higher.
+pub fn theoretical_competitive_ratio() -> f64 {
+    let e = std::f64::consts::E;
+    e / (e - 1.0)
+}
+
+/// Best competitive ratio any deterministic online algorithm can guarantee. In
+/// discrete time `NaiveInstance` stays within it, approaching it as the recover cost
+/// grows relative to the keep cost.
+pub fn deterministic_lower_bound() -> f64 {
+    2.0
+}
+
+/// Approximates the offline optimum by coarsening the access list into buckets of
+/// `coarsening` ticks and keeping only the first access in each bucket, returning the
+/// approximate cost along with a bound on its distance from the exact cost.
+///
+/// An idle gap of g ticks costs the optimum min(keep_cost * g, recover_cost). Merging the
+/// accesses of a bucket into its first one only changes the gaps inside that bucket,
+/// which span fewer than `coarsening` ticks, so each bucket that drops accesses
+/// contributes at most min(keep_cost * (coarsening - 1), dropped * recover_cost) error.
+/// Like [`offline_cost`], accesses after `num_ticks` are ignored and the object is
+/// discarded for free after the last access.
+///
+/// # Panics
+///
+/// Panics if `coarsening` is 0, or if the access list is not sorted and distinct, or
+/// accesses tick 0; see [`validate_access_list`].
+pub fn approximate_offline_cost(
+    access_list: &[u64],
+    num_ticks: u64,
+    coarsening: u64,
+    keep_cost: u64,
+    recover_cost: u64,
+) -> (u64, u64) {
+    assert!(coarsening > 0, "buckets must span at least one tick");
+    if let Err(err) = validate_access_list(access_list, u64::MAX) {
+        panic!("invalid access list: {}", err);
+    }
+    let access_list = trim_access_list(access_list, num_ticks);
+    let gap_cost = |idle: u64| (keep_cost * idle).min(recover_cost);
+    let bucket_error = |dropped: u64| (keep_cost * (coarsening - 1)).min(dropped * recover_cost);
+    let mut cost = 0;
+    let mut error_bound = 0;
+    let mut last_kept = 0;
+    let mut last_bucket = None;
+    let mut dropped = 0;
+    for &t in access_list {
+        let bucket = t / coarsening;
+        if last_bucket == Some(bucket) {
+            dropped += 1;
+            continue;
+        }
+        if dropped > 0 {
+            error_bound += bucket_error(dropped);
+        }
+        cost += gap_cost(t - last_kept - 1);
+        last_kept = t;
+        last_bucket = Some(bucket);
+        dropped = 0;
+    }
+    if dropped > 0 {
+        error_bound += bucket_error(dropped);
+    }
+    (cost, error_bound)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn two_competitive() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        // The online keeps for 2 and recovers for 3 over each gap of 3 idle ticks,
+        // while the offline discards right away and recovers for 3.
+        let num_ticks = 8;
+        let access_list = vec![4, 8];
+        let online_instance = NaiveInstance::new(keep_cost, recover_cost);
+        let competitive_ratio = calculate_competitive_ratio(
+            online_instance,
+            keep_cost,
+            recover_cost,
             access_list,
             num_ticks,
         );
-        assert!(competitive_ratio < 1.67);
+        assert_eq!(5.0 / 3.0, competitive_ratio);
+        assert!(competitive_ratio <= 2.0);
+    }
+    #[test]
+    fn fractional_costs() {
+        // Each gap holds 3 idle ticks, which the online keeps for 2 and then recovers
+        // for 2.5, while the offline discards right away and recovers for 2.5.
+        let access_list = vec![4, 8];
+        let ratio = calculate_competitive_ratio(
+            NaiveInstance::new(1.0, 2.5),
+            1.0,
+            2.5,
+            access_list.clone(),
+            8,
+        );
+        assert_eq!(1.8, ratio);
+        let scaled = calculate_competitive_ratio(NaiveInstance::new(2, 5), 2, 5, access_list, 8);
+        assert_eq!(scaled, ratio);
+    }
+    #[test]
+    fn fractional_costs_for_karlin() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let online = KarlinInstance::with_rng(0.5, 2.5, StdRng::seed_from_u64(1));
+        assert!(online.current_continuous_discard_threshold().fract() != 0.0);
+        let access_list: Vec<u64> = (1..=100).map(|i| i * 7).collect();
+        let ratio = calculate_competitive_ratio(online, 0.5, 2.5, access_list, 700);
+        assert!(ratio >= 1.0);
+        assert!(ratio < 2.0);
+    }
+    #[test]
+    #[should_panic(expected = "costs must be non-negative, got -3")]
+    fn negative_costs_are_rejected() {
+        NaiveInstance::new(1, -3);
+    }
+    #[test]
+    fn expected_cost_matches_monte_carlo() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let access_list = vec![3, 7, 8, 15, 30];
+        let num_ticks = 30;
+        for (keep_cost, recover_cost) in [(1, 5), (2, 7)] {
+            let expected = KarlinInstance::expected_cost(&access_list, keep_cost, recover_cost);
+            let mut rng = StdRng::seed_from_u64(3);
+            let runs = 20_000;
+            let mean = (0..runs)
+                .map(|_| {
+                    let online = KarlinInstance::with_rng(keep_cost, recover_cost, &mut rng);
+                    let mut sim = crate::Simulator::new(access_list.clone(), online);
+                    sim.run(num_ticks);
+                    sim.node.total_accrued_cost()
+                })
+                .sum::<f64>()
+                / runs as f64;
+            assert!((mean - expected).abs() < 0.1, "{} vs {}", mean, expected);
+        }
+    }
+    #[test]
+    #[should_panic(expected = "invalid access list: access 1 comes before")]
+    fn unsorted_access_list_is_rejected() {
+        calculate_competitive_ratio(NaiveInstance::new(1, 3), 1, 3, vec![8, 4], 8);
+    }
+    #[test]
+    fn deterministic_discard_and_recovery_counts() {
+        // The object is discarded 2 ticks after each access and recovered by the next.
+        let mut sim = crate::Simulator::new(vec![4, 8, 12], NaiveInstance::new(1, 3));
+        sim.run(12);
+        assert_eq!(3, sim.node.recovery_count());
+        assert_eq!(3, sim.node.discard_count());
+        sim.run(3);
+        assert_eq!(3, sim.node.recovery_count());
+        assert_eq!(4, sim.node.discard_count());
+    }
+    #[test]
+    fn linear_recover_cost_grows_with_idle_time() {
+        let recover = Linear {
+            base: 3.0,
+            per_tick: 1.0,
+        };
+        let mut sim = crate::Simulator::new(
+            vec![5, 20],
+            BreakEvenInstance::with_recover_cost(1, recover, 3),
+        );
+        // Discarded on ticks 3 and 8, so recovered after 2 and then 12 idle ticks.
+        sim.run(5);
+        assert_eq!(5.0, sim.node.last_tick_cost());
+        sim.run(15);
+        assert_eq!(15.0, sim.node.last_tick_cost());
+        assert_eq!(20.0, sim.node.cost_breakdown().recovery_cost);
+    }
+    #[test]
+    fn karlin_samples_for_the_cost_of_an_immediate_recovery() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let recover = Linear {
+            base: 3.0,
+            per_tick: 1.0,
+        };
+        let mut sim = crate::Simulator::new(
+            (1..100).map(|i| i * 7).collect::<Vec<u64>>(),
+            KarlinInstance::with_recover_cost(1, recover, StdRng::seed_from_u64(7)),
+        );
+        sim.run(700);
+        assert!(sim
+            .node
+            .sampled_discard_thresholds()
+            .iter()
+            .all(|&threshold| threshold <= 3.0));
+        // Every recovery comes at least 4 idle ticks after the discard.
+        let breakdown = sim.node.cost_breakdown();
+        assert!(breakdown.num_recoveries > 0);
+        assert!(breakdown.recovery_cost >= 7.0 * breakdown.num_recoveries as f64);
+    }
+    #[test]
+    fn constant_recover_cost_is_the_default() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let access_list: Vec<u64> = (1..100).map(|i| i * 7).collect();
+        let mut default = crate::Simulator::new(
+            access_list.clone(),
+            KarlinInstance::with_rng(1, 3, StdRng::seed_from_u64(7)),
+        );
+        let mut constant = crate::Simulator::new(
+            access_list,
+            KarlinInstance::with_recover_cost(1, Constant(3.0), StdRng::seed_from_u64(7)),
+        );
+        default.run(700);
+        constant.run(700);
+        assert_eq!(
+            default.node.total_accrued_cost(),
+            constant.node.total_accrued_cost()
+        );
+    }
+    #[test]
+    fn offline_discards_earlier_when_keeping_is_expensive() {
+        // Keeping costs 1 per tick until tick 20 and 3 per tick after.
+        let peak_pricing = |t: u64| if t <= 20 { 1 } else { 3 };
+        let access_list = vec![1, 5, 21, 25];
+        let offline = ScheduledOfflineInstance::new(
+            peak_pricing,
+            6,
+            access_list.clone().into_iter().peekable(),
+        );
+        let mut sim = crate::Simulator::new(access_list.clone(), offline);
+        let policies: Vec<Policy> = sim
+            .run_with_trace(25)
+            .iter()
+            .map(|record| record.policy)
+            .collect();
+        // The same 3 idle ticks are kept off-peak but discarded at once on peak.
+        assert_eq!(Policy::Keep, policies[1]);
+        assert_eq!(Policy::Discard, policies[21]);
+        let offline_cost = sim.node.total_accrued_cost();
+        // 3 to keep through the first gap, then 6 to recover on each of ticks 21 and 25.
+        assert_eq!(15.0, offline_cost);
+        let mut sim =
+            crate::Simulator::new(access_list, ScheduledNaiveInstance::new(peak_pricing, 6));
+        sim.run(25);
+        assert!(sim.node.total_accrued_cost() >= offline_cost);
+    }
+    #[test]
+    fn constant_schedule_matches_fixed_keep_cost() {
+        // The run goes on past the last access, which both offlines discard for free.
+        let access_list = vec![4, 8, 12, 30];
+        let run = |instance: &mut dyn Algorithm| {
+            for t in 1..=40 {
+                instance.tick(access_list.contains(&t));
+            }
+            instance.total_accrued_cost()
+        };
+        let peekable = || access_list.clone().into_iter().peekable();
+        assert_eq!(
+            run(&mut OfflineInstance::new(1, 3, peekable())),
+            run(&mut ScheduledOfflineInstance::new(|_| 1, 3, peekable()))
+        );
+        assert_eq!(
+            run(&mut NaiveInstance::new(1, 3)),
+            run(&mut ScheduledNaiveInstance::new(|_| 1, 3))
+        );
+    }
+    #[test]
+    fn offline_discards_for_free_past_the_last_access() {
+        let access_list = vec![4, 8, 12];
+        let offline = OfflineInstance::new(1, 3, access_list.clone().into_iter().peekable());
+        let mut sim = crate::Simulator::new(access_list.clone(), offline);
+        sim.run(12);
+        assert_eq!(9.0, sim.node.total_accrued_cost());
+        assert_eq!(Policy::Keep, sim.node.current_policy());
+        // The object is never recovered again, so the 8 ticks after the last access
+        // cost nothing.
+        sim.run(8);
+        assert_eq!(9.0, sim.node.total_accrued_cost());
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+        assert_eq!(0.0, sim.node.cost_breakdown().time_cost);
+        // Every offline agrees, and the online instances can only pay more.
+        let optimal = crate::optimal_offline::OptimalOffline::two_tier(1, 3, &access_list, 20);
+        assert_eq!(9.0, optimal.minimum_cost());
+        assert_eq!(9.0, offline_cost(1, 3, &access_list, 20));
+        let mut naive = crate::Simulator::new(access_list, NaiveInstance::new(1, 3));
+        naive.run(20);
+        assert!(naive.node.total_accrued_cost() >= 9.0);
+        // A single access long before the run ends costs the optimum its first gap.
+        assert_eq!(3.0, offline_cost(1, 3, &[4], 100));
+        assert!(calculate_competitive_ratio(NaiveInstance::new(1, 3), 1, 3, [4], 100) >= 1.0);
+    }
+    #[test]
+    fn every_strategy_builds_and_runs() {
+        let access_list = vec![4, 8, 12];
+        let strategies = [
+            Strategy::Naive,
+            Strategy::KarlinRandomized,
+            Strategy::Offline {
+                access_list: access_list.clone(),
+            },
+            Strategy::Predictive {
+                predicted_next_access: 4,
+                confidence: 0.5,
+            },
+        ];
+        let mut costs = vec![];
+        for strategy in &strategies {
+            let mut sim = crate::Simulator::new(access_list.clone(), strategy.build(1, 3));
+            sim.run(12);
+            assert!(sim.node.total_accrued_cost().is_finite());
+            costs.push(sim.node.total_accrued_cost());
+        }
+        assert_eq!(15.0, costs[0]);
+        assert_eq!(9.0, costs[2]);
+    }
+    #[test]
+    fn discarded_start_recovers_on_first_access() {
+        let access_list = vec![4, 8, 12];
+        let mut sim = crate::Simulator::new(
+            access_list.clone(),
+            NaiveInstance::with_initial_policy(1, 3, Policy::Discard),
+        );
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+        // Nothing is paid to hold the discarded object before the first access.
+        sim.run(3);
+        assert_eq!(0.0, sim.node.total_accrued_cost());
+        sim.run(1);
+        assert_eq!(3.0, sim.node.last_tick_cost());
+        assert_eq!(1, sim.node.recovery_count());
+        assert_eq!(Policy::Keep, sim.node.current_policy());
+        sim.node.reset();
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+
+        let offline = OfflineInstance::with_initial_policy(
+            1,
+            3,
+            access_list.clone().into_iter().peekable(),
+            Policy::Discard,
+        );
+        let mut sim = crate::Simulator::new(access_list, offline);
+        sim.run(4);
+        assert_eq!(3.0, sim.node.total_accrued_cost());
+    }
+    #[test]
+    #[should_panic(expected = "no compressed tier")]
+    fn compressed_start_is_rejected() {
+        NaiveInstance::with_initial_policy(1, 3, Policy::Compress);
+    }
+    #[test]
+    fn shared_offline_cost_gives_identical_ratios() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let access_list = vec![3, 4, 10, 17, 18, 40];
+        let offline = offline_cost(1, 5, &access_list, 40);
+        let mut sim = crate::Simulator::new(
+            access_list.clone(),
+            OfflineInstance::new(1, 5, access_list.clone().into_iter().peekable()),
+        );
+        sim.run(40);
+        assert_eq!(sim.node.total_accrued_cost(), offline);
+
+        let naive = NaiveInstance::new(1, 5);
+        assert_eq!(
+            calculate_competitive_ratio(naive.clone(), 1, 5, access_list.clone(), 40),
+            crate::online_ratio(naive, &access_list, 40, offline)
+        );
+        let karlin = KarlinInstance::with_rng(1, 5, StdRng::seed_from_u64(5));
+        assert_eq!(
+            calculate_competitive_ratio(karlin.clone(), 1, 5, access_list.clone(), 40),
+            crate::online_ratio(karlin, &access_list, 40, offline)
+        );
+    }
+    #[test]
+    fn borrowed_access_list_gives_identical_results() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut rng = StdRng::seed_from_u64(9);
+        let access_list = crate::util::generate_access_list_with_rng(20_000, 200_000, &mut rng);
+        let num_ticks = *access_list.last().unwrap();
+        let karlin = KarlinInstance::with_rng(1, 20, StdRng::seed_from_u64(4));
+        assert_eq!(
+            calculate_competitive_ratio(karlin.clone(), 1, 20, access_list.clone(), num_ticks),
+            calculate_competitive_ratio(karlin, 1, 20, &access_list, num_ticks)
+        );
+        let mut owned = crate::Simulator::new(access_list.clone(), NaiveInstance::new(1, 20));
+        owned.run(num_ticks);
+        let mut borrowed =
+            crate::Simulator::new(access_list.iter().copied(), NaiveInstance::new(1, 20));
+        borrowed.run(num_ticks);
+        assert_eq!(owned.node.cost_breakdown(), borrowed.node.cost_breakdown());
+        assert_eq!(
+            owned.node.total_accrued_cost(),
+            borrowed.node.total_accrued_cost()
+        );
+    }
+    #[test]
+    fn accesses_after_the_last_tick_are_ignored() {
+        // The run ends before the access at tick 12, so the optimum discards for free
+        // after tick 8, as it does past the last access of any run.
+        assert_eq!(6.0, offline_cost(1, 3, &[4, 8, 12], 10));
+        assert_eq!(
+            offline_cost(1, 3, &[4, 8], 10),
+            offline_cost(1, 3, &[4, 8, 12], 10)
+        );
+        let naive = NaiveInstance::new(1, 3);
+        assert_eq!(
+            calculate_competitive_ratio(naive.clone(), 1, 3, [4, 8], 10),
+            calculate_competitive_ratio(naive, 1, 3, [4, 8, 12], 10)
+        );
+        assert_eq!(&[4, 8], crate::util::trim_access_list(&[4, 8, 12, 20], 11));
+    }
+    #[test]
+    fn hysteresis_recovers_less_often_in_bursts() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let (keep_cost, recover_cost) = (1, 3);
+        // Bursts of three accesses, each after 3 idle ticks, which the naive instance
+        // discards just before every access.
+        let access_list = vec![4, 8, 12, 40, 44, 48, 80, 84, 88];
+        let num_ticks = 88;
+        let mut naive = crate::Simulator::new(
+            access_list.iter().copied(),
+            NaiveInstance::new(keep_cost, recover_cost),
+        );
+        naive.run(num_ticks);
+        let hysteresis = HysteresisInstance::new(keep_cost, recover_cost, 10);
+        let mut sim = crate::Simulator::new(access_list.iter().copied(), hysteresis.clone());
+        sim.run(num_ticks);
+        assert_eq!(9, naive.node.recovery_count());
+        // Only the first access of each burst recovers.
+        assert_eq!(3, sim.node.recovery_count());
+        assert!(sim.node.total_accrued_cost() < naive.node.total_accrued_cost());
+        let ratio = calculate_competitive_ratio(
+            hysteresis,
+            keep_cost,
+            recover_cost,
+            &access_list,
+            num_ticks,
+        );
+        crate::assert_ratio_bounds(ratio, 1.0, 2.0);
+
+        // Without a hold time it matches the naive instance.
+        let mut rng = StdRng::seed_from_u64(11);
+        let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+        let num_ticks = *access_list.last().unwrap();
+        let mut naive = crate::Simulator::new(
+            access_list.iter().copied(),
+            NaiveInstance::new(keep_cost, recover_cost),
+        );
+        naive.run(num_ticks);
+        let mut sim = crate::Simulator::new(
+            access_list.iter().copied(),
+            HysteresisInstance::new(keep_cost, recover_cost, 0),
+        );
+        sim.run(num_ticks);
+        assert_eq!(naive.node.cost_breakdown(), sim.node.cost_breakdown());
+    }
+    #[test]
+    fn always_keep_pays_every_idle_tick() {
+        let access_list = vec![4, 8, 12];
+        let mut sim = crate::Simulator::new(access_list.clone(), AlwaysKeepInstance::new(2));
+        sim.run(20);
+        assert_eq!(2.0 * (20 - 3) as f64, sim.node.total_accrued_cost());
+        assert_eq!(0, sim.node.discard_count());
+        assert_eq!(0, sim.node.recovery_count());
+        let ratio = calculate_competitive_ratio(AlwaysKeepInstance::new(1), 1, 3, access_list, 12);
+        assert_eq!(1.0, ratio);
+    }
+    #[test]
+    fn always_discard_recovers_on_every_access() {
+        let mut sim = crate::Simulator::new(vec![4, 8, 12], AlwaysDiscardInstance::new(3));
+        sim.run(20);
+        assert_eq!(3.0 * 3.0, sim.node.total_accrued_cost());
+        assert_eq!(0.0, sim.node.cost_breakdown().time_cost);
+        assert_eq!(Policy::Discard, sim.node.current_policy());
+        // An access on the first tick finds the object still in the top tier, as does
+        // one right after another access.
+        let mut sim = crate::Simulator::new(vec![1, 5, 6, 9], AlwaysDiscardInstance::new(3));
+        sim.run(9);
+        assert_eq!(3.0 * 2.0, sim.node.total_accrued_cost());
+        assert_eq!(2, sim.node.recovery_count());
+    }
+    #[test]
+    fn instances_report_their_competitive_bounds() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let (keep_cost, recover_cost) = (1, 3);
+        let offline = OfflineInstance::new(keep_cost, recover_cost, vec![4].into_iter().peekable());
+        assert_eq!(1.0, offline.competitive_bound());
+        assert_eq!(
+            2.0,
+            NaiveInstance::new(keep_cost, recover_cost).competitive_bound()
+        );
+        let karlin = KarlinInstance::with_rng(keep_cost, 20, StdRng::seed_from_u64(1));
+        assert!((analytic_worst_case_ratio(20) - karlin.competitive_bound()).abs() < 1e-12);
+        assert!(karlin.competitive_bound() > theoretical_competitive_ratio());
+        assert!(karlin.competitive_bound() < theoretical_competitive_ratio() + 0.02);
+        for recover_cost in [1, 3, 7, 100] {
+            let karlin = KarlinInstance::with_rng(1, recover_cost, StdRng::seed_from_u64(1));
+            assert!(karlin.competitive_bound() <= deterministic_lower_bound());
+        }
+        let uniform = KarlinInstance::with_distribution(
+            keep_cost,
+            20,
+            karlin::UniformDistribution,
+            StdRng::seed_from_u64(1),
+        );
+        assert!(uniform.competitive_bound() > karlin.competitive_bound());
+        assert_eq!(
+            f64::INFINITY,
+            AlwaysKeepInstance::new(keep_cost).competitive_bound()
+        );
+        let boxed: Box<dyn Algorithm> = Box::new(NaiveInstance::new(keep_cost, recover_cost));
+        assert_eq!(2.0, boxed.competitive_bound());
+
+        let mut rng = StdRng::seed_from_u64(5);
+        for (keep_cost, recover_cost) in [(1, 3), (2, 7), (1, 20)] {
+            let naive = NaiveInstance::new(keep_cost, recover_cost);
+            let mut karlin_total = 0.0;
+            let runs = 200;
+            for _ in 0..runs {
+                let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+                let num_ticks = *access_list.last().unwrap();
+                let ratio = calculate_competitive_ratio(
+                    naive.clone(),
+                    keep_cost,
+                    recover_cost,
+                    access_list.clone(),
+                    num_ticks,
+                );
+                assert!(ratio <= naive.competitive_bound(), "ratio {}", ratio);
+                let karlin = KarlinInstance::with_rng(
+                    keep_cost,
+                    recover_cost,
+                    StdRng::seed_from_u64(rng.gen()),
+                );
+                karlin_total += calculate_competitive_ratio(
+                    karlin,
+                    keep_cost,
+                    recover_cost,
+                    access_list,
+                    num_ticks,
+                );
+            }
+            let karlin =
+                KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(0));
+            assert!(karlin_total / runs as f64 <= karlin.competitive_bound());
+        }
+    }
+    #[test]
+    fn current_discard_threshold_is_resampled_after_access() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut online = KarlinInstance::with_rng(1, 5, StdRng::seed_from_u64(11));
+        for t in 1..=100 {
+            let access = t % 4 == 0;
+            online.tick(access);
+            if access {
+                let threshold = online.current_discard_threshold();
+                assert!((0..=5).contains(&threshold));
+                assert_eq!(
+                    Some(&(threshold as f64)),
+                    online.sampled_discard_thresholds().last()
+                );
+            }
+        }
+    }
+    #[test]
+    fn back_to_back_accesses_never_recover() {
+        use crate::hint::{HintedInstance, NoHint};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let access_list: Vec<u64> = (1..=200).collect();
+        let mut karlin = crate::Simulator::new(
+            access_list.clone(),
+            KarlinInstance::with_rng(1, 3, StdRng::seed_from_u64(2)),
+        );
+        let mut primal_dual = crate::Simulator::new(
+            access_list.clone(),
+            PrimalDualInstance::with_rng(1, 3, StdRng::seed_from_u64(2)),
+        );
+        let mut hinted = crate::Simulator::new(
+            access_list,
+            HintedInstance::with_rng(1, 3, NoHint, StdRng::seed_from_u64(2)),
+        );
+        karlin.run(200);
+        primal_dual.run(200);
+        hinted.run(200);
+        assert_eq!(0.0, karlin.node.total_accrued_cost());
+        assert_eq!(0.0, primal_dual.node.total_accrued_cost());
+        assert_eq!(0.0, hinted.node.total_accrued_cost());
+    }
+    #[test]
+    fn zero_and_unit_thresholds_discard_on_different_ticks() {
+        // Thresholds of 0 and 1 sampled from draws near 0 and just above the mass of 0.
+        let zero = karlin::normalized_pdf(0, 3) / 2.0;
+        let one = karlin::normalized_pdf(0, 3) + karlin::normalized_pdf(1, 3) / 2.0;
+        for (u, threshold) in [(zero, 0), (one, 1)] {
+            let word = ((u * (1u64 << 53) as f64) as u64) << 11;
+            let mut online = KarlinInstance::with_rng(1, 3, ScriptedRng(vec![word].into_iter()));
+            assert_eq!(threshold, online.current_discard_threshold());
+            let discarded_at = threshold + 1;
+            for t in 1..=3 {
+                online.tick(false);
+                let discarded = t >= discarded_at;
+                assert_eq!(
+                    discarded,
+                    matches!(online.current_policy(), Policy::Discard)
+                );
+            }
+        }
+    }
+    #[test]
+    fn continuous_discard_threshold_rounds_up() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let online = KarlinInstance::continuous_with_rng(1, 5, StdRng::seed_from_u64(11));
+        let exact = online.current_continuous_discard_threshold();
+        assert!(exact.fract() != 0.0);
+        assert_eq!(exact.ceil() as u64, online.current_discard_threshold());
+    }
+    #[test]
+    fn ticks_up_to_the_maximum_horizon() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let start = u64::MAX - 10;
+        let mut naive = BreakEvenInstance::new(u64::MAX / 2, 3).starting_at(start);
+        let mut karlin =
+            KarlinInstance::with_rng(1, 3, StdRng::seed_from_u64(0)).starting_at(start);
+        for t in start + 1..=u64::MAX {
+            naive.tick(t == u64::MAX);
+            karlin.tick(t == u64::MAX);
+        }
+        // The keep cost paid saturates rather than overflowing, so it discards at once
+        // and recovers on the final access.
+        assert_eq!(3.0, naive.total_accrued_cost());
+        assert_eq!(1, karlin.recovery_count());
+    }
+    #[test]
+    #[should_panic(expected = "past tick u64::MAX")]
+    fn ticking_past_the_maximum_horizon_panics() {
+        let mut naive = BreakEvenInstance::new(1, 3).starting_at(u64::MAX);
+        naive.tick(false);
+    }
+    #[test]
+    fn karlin_beats_uniform_waits_on_average() {
+        use crate::analysis::competitive_ratio_stats_seeded;
+        use crate::karlin::UniformDistribution;
+        let karlin = competitive_ratio_stats_seeded(
+            |rng| KarlinInstance::with_rng(1, 20, rng),
+            1,
+            20,
+            200,
+            7,
+        );
+        let uniform = competitive_ratio_stats_seeded(
+            |rng| KarlinInstance::with_distribution(1, 20, UniformDistribution, rng),
+            1,
+            20,
+            200,
+            7,
+        );
+        assert!(
+            karlin.mean < uniform.mean,
+            "karlin {} uniform {}",
+            karlin.mean,
+            uniform.mean
+        );
+    }
+    #[test]
+    fn empty_access_list_has_unit_ratio() {
+        let ratio = calculate_competitive_ratio(NaiveInstance::new(1, 3), 1, 3, vec![], 0);
+        assert_eq!(1.0, ratio);
+    }
+    #[test]
+    fn zero_offline_cost_has_unit_ratio() {
+        // An access on the first tick finds the object in the top tier, so neither
+        // instance pays anything.
+        let ratio = calculate_competitive_ratio(NaiveInstance::new(1, 3), 1, 3, vec![1], 1);
+        assert_eq!(1.0, ratio);
+    }
+    #[test]
+    fn randomized_competitive() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let num_ticks = 12;
+        let access_list = vec![4, 8, 12];
+        let rng = StdRng::seed_from_u64(42);
+        let online_instance = KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+        let competitive_ratio = calculate_competitive_ratio(
+            online_instance,
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        crate::assert_ratio_bounds(competitive_ratio, 1.0, 1.67);
+    }
+    #[test]
+    fn offline_never_exceeds_online() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let naive = NaiveInstance::new(keep_cost, recover_cost);
+            let karlin =
+                KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(rng.gen()));
+            let continuous = KarlinInstance::continuous_with_rng(
+                keep_cost,
+                recover_cost,
+                StdRng::seed_from_u64(rng.gen()),
+            );
+            let ratios = [
+                calculate_competitive_ratio(
+                    naive,
+                    keep_cost,
+                    recover_cost,
+                    access_list.clone(),
+                    num_ticks,
+                ),
+                calculate_competitive_ratio(
+                    karlin,
+                    keep_cost,
+                    recover_cost,
+                    access_list.clone(),
+                    num_ticks,
+                ),
+                calculate_competitive_ratio(
+                    continuous,
+                    keep_cost,
+                    recover_cost,
+                    access_list.clone(),
+                    num_ticks,
+                ),
+            ];
+            for ratio in ratios {
+                assert!(ratio >= 1.0, "online beat offline on {:?}", access_list);
+            }
+        }
+    }
+    #[test]
+    fn approximate_offline_within_bound() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let access_list = crate::util::generate_access_list_with_rng(200, 1_000, &mut rng);
+            // Run past the last access, and stop short of it, to cover both ends.
+            for num_ticks in [1_100, 500] {
+                let exact = offline_cost(keep_cost, recover_cost, &access_list, num_ticks) as u64;
+                assert_eq!(
+                    (exact, 0),
+                    approximate_offline_cost(&access_list, num_ticks, 1, keep_cost, recover_cost)
+                );
+                for coarsening in [5, 20] {
+                    let (approx, bound) = approximate_offline_cost(
+                        &access_list,
+                        num_ticks,
+                        coarsening,
+                        keep_cost,
+                        recover_cost,
+                    );
+                    assert!(approx.abs_diff(exact) <= bound);
+                }
+            }
+        }
+    }
+    #[test]
+    #[should_panic(expected = "invalid access list")]
+    fn approximate_offline_rejects_access_at_tick_zero() {
+        approximate_offline_cost(&[0, 4], 10, 1, 1, 3);
+    }
+    #[test]
+    fn cloud_pricing() {
+        // A 100GB object billed daily in cents: hot storage at $0.023/GB-month, and
+        // restoring from archive at a $0.01/GB retrieval fee plus $0.02/GB egress.
+        let costs = CloudCosts {
+            storage_cost: 8,
+            retrieval_fee: 100,
+            egress_cost: 200,
+        };
+        let (keep_cost, recover_cost) = (costs.keep_cost(), costs.recover_cost());
+        let offline_cost = |access_list: Vec<u64>| {
+            let num_ticks = *access_list.last().unwrap();
+            let offline = OfflineInstance::new(
+                keep_cost,
+                recover_cost,
+                access_list.clone().into_iter().peekable(),
+            );
+            let mut sim = crate::Simulator::new(access_list, offline);
+            sim.run(num_ticks);
+            sim.node.total_accrued_cost()
+        };
+        // Read weekly, holding is cheaper than paying to restore each time.
+        let weekly: Vec<u64> = (1..=52).map(|week| week * 7).collect();
+        assert_eq!((52 * 6 * 8) as f64, offline_cost(weekly.clone()));
+        // Read quarterly, archiving between reads is cheaper.
+        let quarterly: Vec<u64> = (1..=4).map(|quarter| quarter * 91).collect();
+        assert_eq!((4 * 300) as f64, offline_cost(quarterly.clone()));
+        for access_list in [weekly, quarterly] {
+            let num_ticks = *access_list.last().unwrap();
+            let online = NaiveInstance::new(keep_cost, recover_cost);
+            let ratio = calculate_competitive_ratio(
+                online,
+                keep_cost,
+                recover_cost,
+                access_list,
+                num_ticks,
+            );
+            assert!((1.0..=2.0).contains(&ratio));
+        }
+    }
+    #[test]
+    fn shared_randomness_reduces_variance() {
+        use crate::util::{generate_exact_access_list, SharedRng};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let variance = |xs: &[f64]| {
+            let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+            xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (xs.len() - 1) as f64
+        };
+        let mut lists = StdRng::seed_from_u64(1);
+        let mut shared_diffs = vec![];
+        let mut independent_diffs = vec![];
+        for seed in 0..1_000 {
+            let access_list = generate_exact_access_list(5, 50, &mut lists).unwrap();
+            let num_ticks = *access_list.last().unwrap();
+            let shared = SharedRng::new(seed);
+            let independent = SharedRng::new(seed + 1_000_000);
+            let karlin = calculate_competitive_ratio(
+                KarlinInstance::with_rng(keep_cost, recover_cost, shared.stream()),
+                keep_cost,
+                recover_cost,
+                access_list.clone(),
+                num_ticks,
+            );
+            let primal_dual = |rng: SharedRng| {
+                calculate_competitive_ratio(
+                    PrimalDualInstance::with_rng(keep_cost, recover_cost, rng.stream()),
+                    keep_cost,
+                    recover_cost,
+                    access_list.clone(),
+                    num_ticks,
+                )
+            };
+            shared_diffs.push(karlin - primal_dual(shared));
+            independent_diffs.push(karlin - primal_dual(independent));
+        }
+        assert!(variance(&shared_diffs) < variance(&independent_diffs));
+    }
+    #[test]
+    fn learned_with_perfect_prediction() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 20u64;
+        // Long and short gaps predicted correctly pay close to the offline as lambda
+        // shrinks.
+        for gap in [100, 4] {
+            let mut consistencies = vec![];
+            for (seed, lambda) in [1.0, 0.5, 0.1].into_iter().enumerate() {
+                let online = LearnedInstance::with_rng(
+                    keep_cost,
+                    recover_cost,
+                    gap,
+                    lambda,
+                    StdRng::seed_from_u64(seed as u64),
+                );
+                let consistency = online.consistency();
+                let access_list: Vec<u64> = (1..=10_000).map(|i| i * gap).collect();
+                let num_ticks = *access_list.last().unwrap();
+                let ratio = calculate_competitive_ratio(
+                    online,
+                    keep_cost,
+                    recover_cost,
+                    access_list,
+                    num_ticks,
+                );
+                assert!((ratio - consistency).abs() < 0.05 * consistency);
+                consistencies.push(consistency);
+            }
+            assert!(consistencies.windows(2).all(|pair| pair[1] < pair[0]));
+            assert!(consistencies[2] < 1.03);
+        }
+    }
+    #[test]
+    fn learned_robustness_against_theoretical_ratio() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 100u64;
+        for predicted_next_access in [500, 20] {
+            // Ignoring the prediction is as robust as the optimal randomized algorithm,
+            // slightly more so in discrete time.
+            let robust = LearnedInstance::new(keep_cost, recover_cost, predicted_next_access, 1.0);
+            assert!(robust.robustness() <= theoretical_competitive_ratio());
+            assert!(robust.robustness() > theoretical_competitive_ratio() - 0.005);
+            let mut robustness = vec![robust.robustness()];
+            for (seed, lambda) in [0.5, 0.1].into_iter().enumerate() {
+                let online = LearnedInstance::with_rng(
+                    keep_cost,
+                    recover_cost,
+                    predicted_next_access,
+                    lambda,
+                    StdRng::seed_from_u64(seed as u64),
+                );
+                assert!(online.robustness() > theoretical_competitive_ratio());
+                robustness.push(online.robustness());
+                // The gap the prediction is most wrong about is charged the robustness.
+                let waits = &online.inner.sampler().waits;
+                let worst_gap = (1..=1_000)
+                    .max_by(|&a, &b| {
+                        let ratio = |idle| gap_ratio(1.0, recover_cost as f64, waits, idle);
+                        ratio(a).total_cmp(&ratio(b))
+                    })
+                    .unwrap();
+                let expected = online.robustness();
+                let access_list: Vec<u64> = (1..=1_000).map(|i| i * (worst_gap + 1)).collect();
+                let num_ticks = *access_list.last().unwrap();
+                let ratio = calculate_competitive_ratio(
+                    online,
+                    keep_cost,
+                    recover_cost,
+                    access_list,
+                    num_ticks,
+                );
+                assert!((ratio - expected).abs() < 0.05 * expected);
+            }
+            assert!(robustness.windows(2).all(|pair| pair[1] > pair[0]));
+        }
+    }
+    #[test]
+    fn predictive_with_perfect_prediction() {
+        let keep_cost = 1u64;
+        let recover_cost = 100u64;
+        for gap in [1_000, 20] {
+            let access_list: Vec<u64> = (1..=50).map(|i| i * gap).collect();
+            let num_ticks = *access_list.last().unwrap();
+            let online = PredictiveInstance::new(keep_cost, recover_cost, gap, 1.0);
+            assert_eq!(1.0, online.consistency());
+            let ratio = calculate_competitive_ratio(
+                online,
+                keep_cost,
+                recover_cost,
+                access_list,
+                num_ticks,
+            );
+            assert_eq!(1.0, ratio);
+        }
+    }
+    #[test]
+    fn predictive_with_adversarial_prediction() {
+        let keep_cost = 1u64;
+        let recover_cost = 100u64;
+        // Each gap is just long enough to reach the discard threshold.
+        for (predicted_next_access, gap) in [(1_000, 51), (20, 201)] {
+            let access_list: Vec<u64> = (1..=200).map(|i| i * gap).collect();
+            let num_ticks = *access_list.last().unwrap();
+            let online =
+                PredictiveInstance::new(keep_cost, recover_cost, predicted_next_access, 0.5);
+            let robustness = online.robustness();
+            let ratio = calculate_competitive_ratio(
+                online,
+                keep_cost,
+                recover_cost,
+                access_list,
+                num_ticks,
+            );
+            assert!(ratio > 2.5);
+            assert!(ratio <= robustness);
+        }
+    }
+    #[test]
+    fn predictive_without_confidence_matches_naive() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let access_list = crate::util::generate_access_list_with_rng(10, 100, &mut rng);
+            let num_ticks = *access_list.last().unwrap();
+            let naive = NaiveInstance::new(keep_cost, recover_cost);
+            for predicted_next_access in [1, 100] {
+                let predictive =
+                    PredictiveInstance::new(keep_cost, recover_cost, predicted_next_access, 0.0);
+                assert_eq!(
+                    calculate_competitive_ratio(
+                        naive.clone(),
+                        keep_cost,
+                        recover_cost,
+                        access_list.clone(),
+                        num_ticks
+                    ),
+                    calculate_competitive_ratio(
+                        predictive,
+                        keep_cost,
+                        recover_cost,
+                        access_list.clone(),
+                        num_ticks
+                    )
+                );
+            }
+        }
+    }
+    #[test]
+    fn reset_reproduces_deterministic_runs() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let access_list = vec![4, 8, 12, 20, 21, 35];
+        let num_ticks = 35;
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let naive = NaiveInstance::new(keep_cost, recover_cost);
+        let mut offline_sim = crate::Simulator::new(access_list.clone(), offline);
+        let mut naive_sim = crate::Simulator::new(access_list.clone(), naive);
+        offline_sim.run(num_ticks);
+        naive_sim.run(num_ticks);
+        let costs = [
+            offline_sim.node.total_accrued_cost(),
+            naive_sim.node.total_accrued_cost(),
+        ];
+
+        let mut offline = offline_sim.node;
+        let mut naive = naive_sim.node;
+        offline.reset();
+        naive.reset();
+        assert_eq!(0.0, offline.total_accrued_cost());
+        let mut offline_sim = crate::Simulator::new(access_list.clone(), offline);
+        let mut naive_sim = crate::Simulator::new(access_list, naive);
+        offline_sim.run(num_ticks);
+        naive_sim.run(num_ticks);
+        assert_eq!(
+            costs,
+            [
+                offline_sim.node.total_accrued_cost(),
+                naive_sim.node.total_accrued_cost(),
+            ]
+        );
+    }
+    #[test]
+    fn policy_timeline() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let access_list = vec![4, 8];
+        let timeline = |instance: &mut dyn Algorithm| {
+            (1..=8)
+                .map(|t| {
+                    instance.tick(access_list.contains(&t));
+                    format!("{:?}", instance.current_policy())
+                })
+                .collect::<Vec<_>>()
+        };
+        // The deterministic instance discards once it has kept for C ticks, and
+        // recovers on the following access.
+        let mut naive = NaiveInstance::new(keep_cost, recover_cost);
+        assert_eq!(
+            ["Keep", "Keep", "Discard", "Keep", "Keep", "Keep", "Discard", "Keep"],
+            timeline(&mut naive)[..]
+        );
+        // The offline instance discards right away whenever the next access is at
+        // least C ticks out.
+        let mut offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        assert_eq!(
+            ["Discard", "Discard", "Discard", "Keep", "Discard", "Discard", "Discard", "Keep"],
+            timeline(&mut offline)[..]
+        );
+    }
+    #[test]
+    fn offline_skips_accesses_behind_the_clock() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let offline_cost = |access_list: Vec<u64>| {
+            let offline = OfflineInstance::new(
+                keep_cost,
+                recover_cost,
+                access_list.clone().into_iter().peekable(),
+            );
+            let mut sim = crate::Simulator::new(access_list, offline);
+            sim.run(8);
+            sim.node.total_accrued_cost()
+        };
+        // Tick 0 is never simulated and the duplicate 4 is behind the clock once the
+        // first one is served; both behave as if absent.
+        assert_eq!(offline_cost(vec![4, 8]), offline_cost(vec![0, 4, 8]));
+        assert_eq!(offline_cost(vec![4, 8]), offline_cost(vec![4, 4, 8]));
+    }
+    /// Yields a fixed sequence of words, used to steer the sampled thresholds.
+    struct ScriptedRng(std::vec::IntoIter<u64>);
+    impl rand::RngCore for ScriptedRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+        fn next_u64(&mut self) -> u64 {
+            self.0.next().unwrap_or(0)
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(8) {
+                let bytes = self.next_u64().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+    #[test]
+    fn break_even_threshold_minimizes_worst_case_ratio() {
+        let keep_cost = 1u64;
+        let recover_cost = 20u64;
+        // Worst case over access lists that repeat a single gap, which is the
+        // adversary's best strategy against a fixed threshold.
+        let worst_case_ratio = |discard_threshold: u64| {
+            (2..=3 * recover_cost)
+                .map(|gap| {
+                    let access_list: Vec<u64> = (1..=10).map(|i| i * gap).collect();
+                    let num_ticks = *access_list.last().unwrap();
+                    let online = BreakEvenInstance::with_threshold(
+                        keep_cost,
+                        recover_cost,
+                        discard_threshold,
+                    );
+                    calculate_competitive_ratio(
+                        online,
+                        keep_cost,
+                        recover_cost,
+                        access_list,
+                        num_ticks,
+                    )
+                })
+                .fold(0.0, f64::max)
+        };
+        let ratios: Vec<f64> = (0..=2 * recover_cost).map(worst_case_ratio).collect();
+        let best_threshold =
+            (0..ratios.len()).fold(0, |best, d| if ratios[d] < ratios[best] { d } else { best });
+        assert!(best_threshold.abs_diff(recover_cost as usize) <= 1);
+        assert!(ratios[best_threshold] <= 2.0 + 1.0 / recover_cost as f64);
+        assert!(ratios[0] > 10.0);
+        assert!(ratios[2 * recover_cost as usize] > 2.5);
+        // The break-even instance is the 2-competitive algorithm.
+        use rand::SeedableRng;
+        let access_list = crate::util::generate_access_list_with_rng(
+            10,
+            100,
+            &mut rand::rngs::StdRng::seed_from_u64(5),
+        );
+        let num_ticks = *access_list.last().unwrap();
+        let mut break_even = crate::Simulator::new(
+            access_list.clone(),
+            BreakEvenInstance::new(keep_cost, recover_cost),
+        );
+        break_even.run(num_ticks);
+        let mut naive =
+            crate::Simulator::new(access_list, NaiveInstance::new(keep_cost, recover_cost));
+        naive.run(num_ticks);
+        assert_eq!(
+            naive.node.total_accrued_cost(),
+            break_even.node.total_accrued_cost()
+        );
+    }
+    #[test]
+    fn worst_case_ratios_approach_theoretical_bounds() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keep_cost = 1u64;
+        let recover_cost = 100u64;
+        // Gaps of C + 1 idle ticks make the randomized algorithm always discard, paying
+        // its threshold and then the recovery, while the offline pays C.
+        let access_list: Vec<u64> = (1..=20_000).map(|i| i * (recover_cost + 2)).collect();
+        let num_ticks = *access_list.last().unwrap();
+        let rng = StdRng::seed_from_u64(0);
+        let randomized = calculate_competitive_ratio(
+            KarlinInstance::with_rng(keep_cost, recover_cost, rng),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        assert!((randomized - analytic_worst_case_ratio(recover_cost)).abs() < 0.01);
+        assert!(randomized < theoretical_competitive_ratio() + 0.02);
+        // Gaps of C idle ticks make the deterministic algorithm keep for C - 1, then
+        // discard and pay to recover, while the offline pays C.
+        let access_list: Vec<u64> = (1..=20_000).map(|i| i * (recover_cost + 1)).collect();
+        let num_ticks = *access_list.last().unwrap();
+        let deterministic = calculate_competitive_ratio(
+            NaiveInstance::new(keep_cost, recover_cost),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        assert!(deterministic <= deterministic_lower_bound());
+        assert!(deterministic > deterministic_lower_bound() - 0.02);
+    }
+    #[test]
+    fn analytic_worst_case_matches_exhaustive_search() {
+        let keep_cost = 1u64;
+        let recover_cost = 3u64;
+        let horizon = 8u64;
+        let pmf: Vec<f64> = (0..=recover_cost)
+            .map(|d| karlin::normalized_pdf(d, recover_cost))
+            .collect();
+        // Words that make the sampler return each threshold, aiming at the middle of
+        // its interval of the cdf.
+        let words: Vec<u64> = (0..pmf.len())
+            .map(|d| {
+                let u = pmf[..d].iter().sum::<f64>() + pmf[d] / 2.0;
+                ((u * (1u64 << 53) as f64) as u64) << 11
+            })
+            .collect();
+        let mut worst: f64 = 0.0;
+        // Every access list over the horizon, including back-to-back accesses.
+        for mask in 1u64..(1 << horizon) {
+            let access_list: Vec<u64> =
+                (1..=horizon).filter(|t| mask >> (t - 1) & 1 == 1).collect();
+            let num_ticks = *access_list.last().unwrap();
+            // Exact expectation over the thresholds sampled before each access.
+            let mut expected_ratio = 0.0;
+            let num_samples = access_list.len() as u32;
+            for combo in 0..pmf.len().pow(num_samples) {
+                let thresholds: Vec<usize> = (0..num_samples)
+                    .map(|i| combo / pmf.len().pow(i) % pmf.len())
+                    .collect();
+                let probability: f64 = thresholds.iter().map(|&d| pmf[d]).product();
+                let rng = ScriptedRng(
+                    thresholds
+                        .iter()
+                        .map(|&d| words[d])
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                );
+                let online = KarlinInstance::with_rng(keep_cost, recover_cost, rng);
+                expected_ratio += probability
+                    * calculate_competitive_ratio(
+                        online,
+                        keep_cost,
+                        recover_cost,
+                        access_list.clone(),
+                        num_ticks,
+                    );
+            }
+            worst = worst.max(expected_ratio);
+        }
+        let analytic = analytic_worst_case_ratio(recover_cost);
+        assert!((analytic - worst).abs() < 1e-9);
+        // The integer thresholds are worse than the continuous bound, but never worse
+        // than the deterministic one, and fall toward the continuous bound as C grows.
+        let ratios: Vec<f64> = [3, 10, 100, 1000]
+            .into_iter()
+            .map(analytic_worst_case_ratio)
+            .collect();
+        assert_eq!(analytic, ratios[0]);
+        assert!(ratios.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(ratios[0] <= deterministic_lower_bound());
+        assert!(ratios[3] > theoretical_competitive_ratio());
+        assert!(ratios[3] < theoretical_competitive_ratio() + 0.001);
     }
 }
@@ -1,13 +1,491 @@
-use rand::{thread_rng, Rng};
-use std::iter::repeat;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessListError {
+    /// More distinct accesses were requested than there are ticks in `[1, max_value]`.
+    TooManyAccesses { len: usize, max_value: u64 },
+    /// The access at `index` comes before the one preceding it.
+    Unsorted { index: usize },
+    /// Tick `tick` is accessed more than once.
+    Duplicate { tick: u64 },
+    /// Tick `tick` falls outside `[1, num_ticks]`.
+    OutOfRange { tick: u64, num_ticks: u64 },
+}
+
+impl fmt::Display for AccessListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessListError::TooManyAccesses { len, max_value } => write!(
+                f,
+                "cannot draw {} distinct accesses from [1, {}]",
+                len, max_value
+            ),
+            AccessListError::Unsorted { index } => {
+                write!(f, "access {} comes before the access preceding it", index)
+            }
+            AccessListError::Duplicate { tick } => write!(f, "tick {} is accessed twice", tick),
+            AccessListError::OutOfRange { tick, num_ticks } => {
+                write!(f, "access at tick {} is outside [1, {}]", tick, num_ticks)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccessListError {}
+
+/// Draws exactly `len` distinct, sorted accesses in `[1, max_value]` using the
+/// thread-local rng.
+///
+/// # Panics
+///
+/// Panics if `len` exceeds `max_value`; use [`generate_exact_access_list`] to handle
+/// that case as an error.
 pub fn generate_access_list(len: usize, max_value: u64) -> Vec<u64> {
-    let mut rng = thread_rng();
-    let mut access_list: Vec<u64> = repeat(0)
-        .take(len)
-        .map(|_: u64| rng.gen_range(1..=max_value as u64))
+    generate_access_list_with_rng(len, max_value, &mut thread_rng())
+}
+
+/// Like [`generate_access_list`], drawing from the provided rng so that workloads can
+/// be reproduced from a seed.
+///
+/// # Panics
+///
+/// Panics if `len` exceeds `max_value`.
+pub fn generate_access_list_with_rng<R: Rng>(len: usize, max_value: u64, rng: &mut R) -> Vec<u64> {
+    generate_exact_access_list(len, max_value, rng).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Generates exactly `len` distinct, sorted accesses in `[1, max_value]` by sampling
+/// without replacement (Floyd's algorithm), so no draws are lost to deduplication.
+pub fn generate_exact_access_list<R: Rng>(
+    len: usize,
+    max_value: u64,
+    rng: &mut R,
+) -> Result<Vec<u64>, AccessListError> {
+    if len as u64 > max_value {
+        return Err(AccessListError::TooManyAccesses { len, max_value });
+    }
+    let mut chosen = BTreeSet::new();
+    for j in (max_value - len as u64 + 1)..=max_value {
+        let t = rng.gen_range(1..=j);
+        if !chosen.insert(t) {
+            chosen.insert(j);
+        }
+    }
+    Ok(chosen.into_iter().collect())
+}
+
+/// Checks that accesses are sorted, distinct and within `[1, num_ticks]`, which the
+/// offline instances rely on to look ahead to the next access.
+pub fn validate_access_list(list: &[u64], num_ticks: u64) -> Result<(), AccessListError> {
+    for (index, pair) in list.windows(2).enumerate() {
+        if pair[1] < pair[0] {
+            return Err(AccessListError::Unsorted { index: index + 1 });
+        }
+        if pair[1] == pair[0] {
+            return Err(AccessListError::Duplicate { tick: pair[0] });
+        }
+    }
+    match list.iter().find(|&&tick| tick == 0 || tick > num_ticks) {
+        Some(&tick) => Err(AccessListError::OutOfRange { tick, num_ticks }),
+        None => Ok(()),
+    }
+}
+
+/// Drops the accesses of a sorted list that fall after `num_ticks`, which a simulation
+/// of `num_ticks` ticks never reaches. An offline instance would otherwise still look
+/// ahead to the first of them when deciding how to hold the object over the last
+/// ticks. Logs a warning when the `log` feature is enabled and accesses are dropped.
+pub fn trim_access_list(list: &[u64], num_ticks: u64) -> &[u64] {
+    let len = list.partition_point(|&tick| tick <= num_ticks);
+    #[cfg(feature = "log")]
+    if len < list.len() {
+        log::warn!(
+            "ignoring {} accesses after tick {}, starting at tick {}",
+            list.len() - len,
+            num_ticks,
+            list[len]
+        );
+    }
+    &list[..len]
+}
+
+/// Sorts and deduplicates accesses after clamping each into `[1, num_ticks]`, so the
+/// result passes [`validate_access_list`]. Accesses past `num_ticks` become an access
+/// on the last tick, and there are none if `num_ticks` is zero.
+pub fn normalize_access_list(list: &[u64], num_ticks: u64) -> Vec<u64> {
+    if num_ticks == 0 {
+        return vec![];
+    }
+    let accesses: BTreeSet<u64> = list.iter().map(|&tick| tick.clamp(1, num_ticks)).collect();
+    accesses.into_iter().collect()
+}
+
+/// Simulates a Poisson process with `rate` expected accesses per tick over
+/// `[1, horizon]`, drawing exponential inter-arrival times with mean `1 / rate`. Each
+/// arrival at continuous time x accesses tick ceil(x), so arrivals within the same tick
+/// are merged and the mean gap between accessed ticks is `1 / (1 - e^-rate)`, close to
+/// `1 / rate` for small rates.
+///
+/// # Panics
+///
+/// Panics if `rate` is not positive and finite.
+pub fn generate_poisson_access_list<R: Rng>(rate: f64, horizon: u64, rng: &mut R) -> Vec<u64> {
+    assert!(
+        rate.is_finite() && rate > 0.0,
+        "rate must be positive and finite"
+    );
+    let mut access_list = Vec::new();
+    let mut time = 0.0;
+    loop {
+        let u: f64 = rng.gen();
+        time += -(-u).ln_1p() / rate;
+        let t = time.ceil().max(1.0);
+        if t > horizon as f64 {
+            return access_list;
+        }
+        if access_list.last() != Some(&(t as u64)) {
+            access_list.push(t as u64);
+        }
+    }
+}
+
+/// Generates up to `count` accesses in `[1, horizon]` whose gaps follow a Zipf
+/// distribution with exponent `s` over `[1, horizon]`, i.e. a gap of k ticks has
+/// probability proportional to k^-s. Larger exponents make short gaps more likely and
+/// cluster accesses more tightly. Stops early once the next access would fall past
+/// `horizon`.
+pub fn generate_zipf_access_list<R: Rng>(
+    s: f64,
+    horizon: u64,
+    count: usize,
+    rng: &mut R,
+) -> Vec<u64> {
+    let cumulative: Vec<f64> = (1..=horizon)
+        .scan(0.0, |total, k| {
+            *total += (k as f64).powf(-s);
+            Some(*total)
+        })
         .collect();
-    access_list.sort();
-    access_list.dedup();
+    let total = cumulative.last().copied().unwrap_or(0.0);
+    let mut access_list = Vec::with_capacity(count);
+    let mut t = 0;
+    while access_list.len() < count {
+        let u = rng.gen::<f64>() * total;
+        t += cumulative.partition_point(|&c| c <= u) as u64 + 1;
+        if t > horizon {
+            break;
+        }
+        access_list.push(t);
+    }
     access_list
 }
+
+/// Places `len` accesses `recover_cost + 1` ticks apart, the worst case for the
+/// deterministic two-tier algorithm with a unit keep cost: each access arrives just
+/// after it has paid `recover_cost - 1` to keep the object and then discarded it, so
+/// it pays `2 * recover_cost - 1` per access where the offline pays `recover_cost`.
+pub fn generate_adversarial_access_list(recover_cost: u64, len: usize) -> Vec<u64> {
+    (1..=len as u64).map(|i| i * (recover_cost + 1)).collect()
+}
+
+/// Reads a recorded access trace with one tick per line, skipping blank lines and
+/// lines starting with `#`.
+///
+/// Returns an `InvalidData` error on a line that is not a tick, or if the ticks are not
+/// a valid access list; see [`validate_access_list`].
+pub fn load_access_list(path: &Path) -> io::Result<Vec<u64>> {
+    let mut access_list = Vec::new();
+    for line in BufReader::new(std::fs::File::open(path)?).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let tick = line.parse().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid tick {:?}: {}", line, err),
+            )
+        })?;
+        access_list.push(tick);
+    }
+    validate_access_list(&access_list, u64::MAX)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(access_list)
+}
+
+/// Summary of the gaps between consecutive accesses of an access list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapStats {
+    pub mean: f64,
+    pub median: f64,
+    pub min: u64,
+    pub max: u64,
+    /// Number of gaps longer than the recover cost, the ones on which an offline
+    /// instance with a unit keep cost discards rather than keeps.
+    pub exceeding_recover_cost: usize,
+}
+
+/// Summarizes the gaps of a sorted access list, where the first gap runs from tick 0
+/// to the first access as in a simulation. Long gaps favor discarding and short ones
+/// keeping, which explains much of why some lists yield higher ratios than others.
+///
+/// # Panics
+///
+/// Panics if `list` is empty.
+pub fn gap_stats(list: &[u64], recover_cost: u64) -> GapStats {
+    assert!(
+        !list.is_empty(),
+        "at least one access is needed for gap statistics"
+    );
+    let mut gaps: Vec<u64> = std::iter::once(0)
+        .chain(list.iter().copied())
+        .zip(list)
+        .map(|(prev, &t)| t - prev)
+        .collect();
+    gaps.sort_unstable();
+    let n = gaps.len();
+    let median = if n.is_multiple_of(2) {
+        (gaps[n / 2 - 1] + gaps[n / 2]) as f64 / 2.0
+    } else {
+        gaps[n / 2] as f64
+    };
+    GapStats {
+        mean: gaps.iter().sum::<u64>() as f64 / n as f64,
+        median,
+        min: gaps[0],
+        max: gaps[n - 1],
+        exceeding_recover_cost: gaps.iter().filter(|&&gap| gap > recover_cost).count(),
+    }
+}
+
+/// How the access lists of an experiment are generated.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "method", rename_all = "snake_case")
+)]
+pub enum AccessGeneration {
+    /// Exactly `list_len` distinct accesses drawn uniformly from `[1, max_tick]`.
+    Uniform { list_len: usize, max_tick: u64 },
+    /// A Poisson process with `rate` expected accesses per tick over `[1, horizon]`.
+    Poisson { rate: f64, horizon: u64 },
+    /// Up to `count` accesses in `[1, horizon]` with Zipf distributed gaps.
+    Zipf {
+        exponent: f64,
+        horizon: u64,
+        count: usize,
+    },
+}
+
+impl Default for AccessGeneration {
+    fn default() -> Self {
+        AccessGeneration::Uniform {
+            list_len: 10,
+            max_tick: 100,
+        }
+    }
+}
+
+impl AccessGeneration {
+    /// Generates a sorted access list, failing if a uniform list asks for more
+    /// accesses than there are ticks.
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> Result<Vec<u64>, AccessListError> {
+        match *self {
+            AccessGeneration::Uniform { list_len, max_tick } => {
+                generate_exact_access_list(list_len, max_tick, rng)
+            }
+            AccessGeneration::Poisson { rate, horizon } => {
+                Ok(generate_poisson_access_list(rate, horizon, rng))
+            }
+            AccessGeneration::Zipf {
+                exponent,
+                horizon,
+                count,
+            } => Ok(generate_zipf_access_list(exponent, horizon, count, rng)),
+        }
+    }
+}
+
+/// Hands out identical seeded random streams so that randomized algorithms compared
+/// on the same input consume common random numbers. Their decisions stay correlated
+/// wherever they draw randomness in step, which keeps independent noise from inflating
+/// the variance of the difference between their costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedRng {
+    seed: u64,
+}
+
+impl SharedRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+    pub fn stream(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    #[test]
+    fn exact_access_list_length() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (len, max_value) in [(0, 10), (10, 100), (50, 60), (100, 100)] {
+            let list = generate_exact_access_list(len, max_value, &mut rng).unwrap();
+            assert_eq!(len, list.len());
+            assert!(list.windows(2).all(|w| w[0] < w[1]));
+            assert!(list.iter().all(|&t| (1..=max_value).contains(&t)));
+        }
+        assert_eq!(
+            Err(AccessListError::TooManyAccesses {
+                len: 11,
+                max_value: 10
+            }),
+            generate_exact_access_list(11, 10, &mut rng)
+        );
+    }
+    #[test]
+    fn invalid_access_lists_are_rejected() {
+        assert_eq!(Ok(()), validate_access_list(&[1, 4, 10], 10));
+        assert_eq!(Ok(()), validate_access_list(&[], 0));
+        assert_eq!(
+            Err(AccessListError::Unsorted { index: 2 }),
+            validate_access_list(&[1, 4, 3], 10)
+        );
+        assert_eq!(
+            Err(AccessListError::Duplicate { tick: 4 }),
+            validate_access_list(&[1, 4, 4], 10)
+        );
+        assert_eq!(
+            Err(AccessListError::OutOfRange {
+                tick: 0,
+                num_ticks: 10
+            }),
+            validate_access_list(&[0, 4], 10)
+        );
+        assert_eq!(
+            Err(AccessListError::OutOfRange {
+                tick: 11,
+                num_ticks: 10
+            }),
+            validate_access_list(&[4, 11], 10)
+        );
+    }
+    #[test]
+    fn normalized_access_list_is_valid() {
+        let list = normalize_access_list(&[12, 0, 4, 4, 2, 30], 10);
+        assert_eq!(vec![1, 2, 4, 10], list);
+        assert_eq!(Ok(()), validate_access_list(&list, 10));
+        assert!(normalize_access_list(&[3], 0).is_empty());
+    }
+    #[test]
+    fn adversarial_access_list_is_worst_case_for_naive() {
+        use crate::two_tier::{calculate_competitive_ratio, KarlinInstance, NaiveInstance};
+        let (keep_cost, recover_cost) = (1, 100);
+        let access_list = generate_adversarial_access_list(recover_cost, 20);
+        assert_eq!(20, access_list.len());
+        let num_ticks = *access_list.last().unwrap();
+        let deterministic = calculate_competitive_ratio(
+            NaiveInstance::new(keep_cost, recover_cost),
+            keep_cost,
+            recover_cost,
+            access_list.clone(),
+            num_ticks,
+        );
+        assert_eq!(1.99, deterministic);
+        let rng = StdRng::seed_from_u64(7);
+        let randomized = calculate_competitive_ratio(
+            KarlinInstance::with_rng(keep_cost, recover_cost, rng),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        assert!(randomized < deterministic);
+    }
+    #[test]
+    fn access_trace_is_loaded_from_file() {
+        let path = std::env::temp_dir().join(format!("access_trace_{}.txt", std::process::id()));
+        std::fs::write(&path, "# recorded trace\n4\n\n8\n  # midday\n12\n").unwrap();
+        let loaded = load_access_list(&path);
+        std::fs::write(&path, "4\nnoon\n").unwrap();
+        let malformed = load_access_list(&path);
+        std::fs::write(&path, "8\n4\n").unwrap();
+        let unsorted = load_access_list(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(vec![4, 8, 12], loaded.unwrap());
+        assert_eq!(io::ErrorKind::InvalidData, malformed.unwrap_err().kind());
+        assert_eq!(io::ErrorKind::InvalidData, unsorted.unwrap_err().kind());
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            load_access_list(&path).unwrap_err().kind()
+        );
+    }
+    #[test]
+    fn gap_stats_of_evenly_spaced_accesses() {
+        let stats = gap_stats(&[4, 8, 12], 3);
+        assert_eq!(
+            GapStats {
+                mean: 4.0,
+                median: 4.0,
+                min: 4,
+                max: 4,
+                exceeding_recover_cost: 3,
+            },
+            stats
+        );
+        assert_eq!(0, gap_stats(&[4, 8, 12], 4).exceeding_recover_cost);
+        let stats = gap_stats(&[1, 3, 10, 11], 3);
+        assert_eq!((1, 7, 1.5), (stats.min, stats.max, stats.median));
+    }
+    #[test]
+    fn poisson_mean_inter_arrival_time() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rate = 0.01;
+        let list = generate_poisson_access_list(rate, 1_000_000, &mut rng);
+        assert!(list.windows(2).all(|w| w[0] < w[1]));
+        let gaps: Vec<u64> = list.windows(2).map(|w| w[1] - w[0]).collect();
+        let mean = gaps.iter().sum::<u64>() as f64 / gaps.len() as f64;
+        assert!((mean * rate - 1.0).abs() < 0.02, "mean gap {}", mean);
+    }
+    #[test]
+    fn larger_zipf_exponent_clusters_accesses() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let gap_stats = |list: Vec<u64>| {
+            let gaps: Vec<f64> = list.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+            let n = gaps.len() as f64;
+            let unit_fraction = gaps.iter().filter(|&&gap| gap == 1.0).count() as f64 / n;
+            let mean = gaps.iter().sum::<f64>() / n;
+            let variance = gaps.iter().map(|gap| (gap - mean).powi(2)).sum::<f64>() / n;
+            (unit_fraction, variance)
+        };
+        let (light_unit, light_variance) =
+            gap_stats(generate_zipf_access_list(1.5, 1_000_000, 1000, &mut rng));
+        let (heavy_unit, heavy_variance) =
+            gap_stats(generate_zipf_access_list(3.0, 1_000_000, 1000, &mut rng));
+        assert!(heavy_unit > light_unit);
+        assert!(heavy_variance < light_variance);
+
+        let list = generate_zipf_access_list(1.0, 50, 1000, &mut rng);
+        assert!(list.len() < 1000);
+        assert!(list.iter().all(|&t| (1..=50).contains(&t)));
+    }
+    #[test]
+    fn access_list_has_exact_length() {
+        for _ in 0..20 {
+            let list = generate_access_list(50, 60);
+            assert_eq!(50, list.len());
+            assert!(list.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Browser bindings for the two-tier simulation, enabled with the `wasm` feature.
+use crate::simulation::simulate_random_workload;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use wasm_bindgen::prelude::*;
+
+pub use crate::simulation::{simulate, SimulationOutput};
+
+/// Runs the simulation and returns the result as a plain JS object.
+#[wasm_bindgen]
+pub fn run_simulation(
+    keep_cost: u32,
+    recover_cost: u32,
+    access_list: &[u32],
+    seed: u64,
+) -> JsValue {
+    let output = simulate(keep_cost as u64, recover_cost as u64, access_list, seed);
+    serde_wasm_bindgen::to_value(&output).unwrap_or(JsValue::NULL)
+}
+
+/// Generates a random workload from the seed and returns the competitive ratio of
+/// the randomized algorithm on it. Everything is drawn from a seeded `SmallRng`, so
+/// no OS entropy is needed.
+#[wasm_bindgen]
+pub fn run_simulation_wasm(keep_cost: u32, recover_cost: u32, seed: u64) -> f64 {
+    simulate_random_workload(
+        keep_cost as u64,
+        recover_cost as u64,
+        SmallRng::seed_from_u64(seed),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn seeded_entry_point_is_finite_and_reproducible() {
+        let ratio = run_simulation_wasm(1, 3, 42);
+        assert!(ratio.is_finite());
+        assert!(ratio >= 1.0);
+        assert_eq!(ratio, run_simulation_wasm(1, 3, 42));
+    }
+}
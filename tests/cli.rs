@@ -0,0 +1,101 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_storage-optimization-problem"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn explicit_flags() {
+    let args = [
+        "--keep-cost",
+        "2",
+        "--recover-cost",
+        "8",
+        "--runs",
+        "5",
+        "--list-len",
+        "20",
+        "--max-tick",
+        "200",
+        "--seed",
+        "7",
+    ];
+    let stdout = run(&args);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(7, lines.len());
+    for line in &lines[..5] {
+        let (deterministic, randomized) = line
+            .strip_prefix("ratio: deterministic=")
+            .and_then(|rest| rest.split_once(", randomized="))
+            .unwrap();
+        assert!(deterministic.parse::<f64>().unwrap() >= 1.0);
+        assert!(randomized.parse::<f64>().unwrap() >= 1.0);
+    }
+    assert!(lines[5].starts_with("deterministic over 1000 runs: mean="));
+    assert!(lines[6].starts_with("randomized over 1000 runs: mean="));
+    // The seed fixes both the access lists and the sampled thresholds.
+    assert_eq!(stdout, run(&args));
+}
+
+#[test]
+fn default_output() {
+    let stdout = run(&[]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(102, lines.len());
+    assert!(lines[..100]
+        .iter()
+        .all(|line| line.starts_with("ratio: deterministic=")));
+    assert!(lines[100].starts_with("deterministic over 1000 runs: mean="));
+    assert!(lines[101].starts_with("randomized over 1000 runs: mean="));
+}
+
+#[test]
+fn three_tiers() {
+    let stdout = run(&["--tiers", "three", "--runs", "3", "--seed", "7"]);
+    assert_eq!(5, stdout.lines().count());
+}
+
+#[test]
+fn csv_format() {
+    let stdout = run(&["--format", "csv", "--runs", "4", "--seed", "7"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(5, lines.len());
+    assert_eq!(
+        "run_index,access_count,offline_cost,online_cost,competitive_ratio",
+        lines[0]
+    );
+    for (run_index, line) in lines[1..].iter().enumerate() {
+        let columns: Vec<&str> = line.split(',').collect();
+        assert_eq!(5, columns.len());
+        assert_eq!(run_index.to_string(), columns[0]);
+    }
+}
+
+#[test]
+fn stream_from_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_storage-optimization-problem"))
+        .args(["--stream", "--seed", "7"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"4\n8\n12\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(3, lines.len());
+    assert!(lines[2].starts_with("tick 12: online="));
+    assert!(lines[2].contains("offline=9, ratio="));
+}
@@ -0,0 +1,128 @@
+//! Invariants of the two-tier algorithms that should hold for any access list and costs.
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use storage_optimization_problem::analysis::cost_trajectory;
+use storage_optimization_problem::two_tier::{
+    calculate_competitive_ratio, KarlinInstance, NaiveInstance, OfflineInstance,
+};
+
+/// Keep and recover costs, where recovering costs more than a tick of keeping, along
+/// with a sorted access list of distinct ticks.
+fn workload() -> impl Strategy<Value = (u64, u64, Vec<u64>)> {
+    (1u64..10).prop_flat_map(|keep_cost| {
+        (
+            Just(keep_cost),
+            keep_cost + 1..keep_cost * 20,
+            proptest::collection::btree_set(1u64..500, 0..30)
+                .prop_map(|accesses| accesses.into_iter().collect::<Vec<u64>>()),
+        )
+    })
+}
+
+fn num_ticks(access_list: &[u64]) -> u64 {
+    access_list.last().copied().unwrap_or(0)
+}
+
+proptest! {
+    #[test]
+    fn ratios_are_at_least_one(
+        (keep_cost, recover_cost, access_list) in workload(),
+        seed in any::<u64>(),
+        trailing_ticks in 0u64..50
+    ) {
+        let num_ticks = num_ticks(&access_list) + trailing_ticks;
+        let deterministic = calculate_competitive_ratio(
+            NaiveInstance::new(keep_cost, recover_cost),
+            keep_cost,
+            recover_cost,
+            &access_list,
+            num_ticks,
+        );
+        let randomized = calculate_competitive_ratio(
+            KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(seed)),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        prop_assert!(deterministic >= 1.0, "deterministic ratio {}", deterministic);
+        prop_assert!(randomized >= 1.0, "randomized ratio {}", randomized);
+    }
+
+    #[test]
+    fn deterministic_ratio_is_bounded((keep_cost, recover_cost, access_list) in workload()) {
+        let num_ticks = num_ticks(&access_list);
+        let ratio = calculate_competitive_ratio(
+            NaiveInstance::new(keep_cost, recover_cost),
+            keep_cost,
+            recover_cost,
+            access_list,
+            num_ticks,
+        );
+        prop_assert!(ratio <= 2.0, "ratio {} above 2", ratio);
+    }
+
+    #[test]
+    fn online_cost_is_at_least_offline(
+        (keep_cost, recover_cost, access_list) in workload(),
+        seed in any::<u64>(),
+        trailing_ticks in 0u64..50
+    ) {
+        let num_ticks = num_ticks(&access_list) + trailing_ticks;
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let offline_cost = cost_trajectory(offline, access_list.clone(), num_ticks)
+            .last()
+            .copied()
+            .unwrap_or(0.0);
+        let online_costs = [
+            cost_trajectory(
+                NaiveInstance::new(keep_cost, recover_cost),
+                access_list.clone(),
+                num_ticks,
+            ),
+            cost_trajectory(
+                KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(seed)),
+                access_list,
+                num_ticks,
+            ),
+        ];
+        for trajectory in online_costs {
+            let online_cost = trajectory.last().copied().unwrap_or(0.0);
+            prop_assert!(online_cost >= offline_cost, "{} < {}", online_cost, offline_cost);
+        }
+    }
+
+    #[test]
+    fn accrued_cost_never_decreases(
+        (keep_cost, recover_cost, access_list) in workload(),
+        seed in any::<u64>()
+    ) {
+        let num_ticks = num_ticks(&access_list);
+        let offline = OfflineInstance::new(
+            keep_cost,
+            recover_cost,
+            access_list.clone().into_iter().peekable(),
+        );
+        let trajectories = [
+            cost_trajectory(offline, access_list.clone(), num_ticks),
+            cost_trajectory(
+                NaiveInstance::new(keep_cost, recover_cost),
+                access_list.clone(),
+                num_ticks,
+            ),
+            cost_trajectory(
+                KarlinInstance::with_rng(keep_cost, recover_cost, StdRng::seed_from_u64(seed)),
+                access_list,
+                num_ticks,
+            ),
+        ];
+        for trajectory in trajectories {
+            prop_assert!(trajectory.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+}